@@ -0,0 +1,81 @@
+// in `day01/src/lib.rs`
+
+// anyhow is a crate that helps with error handling; it comes with an error type that can contain any other error.
+// So the definition of anyhow::Result is actually: `pub type Result<T, E = Error> = core::result::Result<T, E>;`
+// And the Error here is anyhow::Error.
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+
+/// The entries that were found to sum to 2020, along with their sum and product.
+#[derive(Debug, PartialEq)]
+pub struct Answer {
+    pub values: Vec<i64>,
+    pub sum: i64,
+    pub product: i64,
+}
+
+impl Answer {
+    fn new(values: Vec<i64>) -> Self {
+        let sum = values.iter().sum();
+        let product = values.iter().product();
+        Self { values, sum, product }
+    }
+}
+
+fn parse(input: &str) -> Result<Vec<i64>> {
+    // we parse Iterator<Item = &str> values to Iterator<Item = i64> values
+    // "?" after collect() takes Result<T, E> and evaluates to a T (or returns early with the error)
+    Ok(input.lines().map(str::parse::<i64>).collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Part 1: find the two entries that sum to 2020
+pub fn part1(input: &str) -> Result<Answer> {
+    let (a, b) = parse(input)?
+        .into_iter()
+        .tuple_combinations()
+        .filter(|(a, b)| a != b)
+        .find(|(a, b)| a + b == 2020)
+        .ok_or_else(|| anyhow!("no pair had a sum of 2020"))?;
+
+    Ok(Answer::new(vec![a, b]))
+}
+
+/// Part 2: find the three entries that sum to 2020
+pub fn part2(input: &str) -> Result<Answer> {
+    let (a, b, c) = parse(input)?
+        .into_iter()
+        .tuple_combinations()
+        .find(|(a, b, c)| a + b + c == 2020)
+        .ok_or_else(|| anyhow!("no tuple of length 3 had a sum of 2020"))?;
+
+    Ok(Answer::new(vec![a, b, c]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = include_str!("sample.txt");
+
+    #[test]
+    fn test_part1() {
+        let answer = part1(SAMPLE).unwrap();
+        assert_eq!(answer.values, vec![1721, 299]);
+        assert_eq!(answer.sum, 2020);
+        assert_eq!(answer.product, 514579);
+    }
+
+    #[test]
+    fn test_part2() {
+        let answer = part2(SAMPLE).unwrap();
+        assert_eq!(answer.values, vec![979, 366, 675]);
+        assert_eq!(answer.sum, 2020);
+        assert_eq!(answer.product, 241861950);
+    }
+
+    #[test]
+    fn test_no_match() {
+        assert!(part1("1\n2\n3").is_err());
+        assert!(part2("1\n2\n3").is_err());
+    }
+}
@@ -1,58 +1,23 @@
 // in `day01/src/main.rs
 
-// anyhow is a crate that helps with error handling; it comes with an error type that can contain any other error.
-// So the definition of anyhow::Result is actually: `pub type Result<T, E = Error> = core::result::Result<T, E>;`
-// And the Error here is anyhow::Error.
-use anyhow::Result;
-use itertools::Itertools;
+use day01::{part1, part2};
 
 fn main() -> anyhow::Result<()> {
-    /*
-    let pair = find_pair_whose_sum_is_2020(
-        // include input.txt at compile-time
-        // split by newlines, producing a stream of items
-        // we parse Iterator<Item = &str> values to Iterator<Item = i64> values
-        // unwrap all the items retrieved from the iterator
-        // "?" after collect() acts like unwrap(); takes Result<T, E> and evaluates to a T
-        include_str!("input.txt")
-            .split('\n')
-            .map(str::parse::<i64>)
-            .collect::<Result<Vec<_>, _>>()?,
-    );
-    dbg!(pair);
-    Ok(())
-    */
+    let input = include_str!("input.txt");
 
     // Part 1: find the two entries that sum to 2020
-    let (a, b) = include_str!("input.txt")
-        .split('\n')
-        .map(str::parse::<i64>)
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter()
-        .tuple_combinations()
-        .filter(|(a, b)| a != b)
-        .find(|(a, b)| a + b == 2020)
-        .expect("no pair had a sum of 2020");
-
+    let answer = part1(input)?;
     println!("part 1:");
-    println!("  a: {}  b: {}", a, b);
-    println!("  a + b = {}", a + b);
-    println!("  a * b = {}", a * b);
+    println!("  a: {}  b: {}", answer.values[0], answer.values[1]);
+    println!("  a + b = {}", answer.sum);
+    println!("  a * b = {}", answer.product);
 
     // Part 2: find the three entries that sum to 2020
-    let (a, b, c) = include_str!("input.txt")
-        .split('\n')
-        .map(str::parse::<i64>)
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter()
-        .tuple_combinations()
-        .find(|(a, b, c)| a + b + c == 2020)
-        .expect("no tuple of length 3 had a sum of 2020");
-
+    let answer = part2(input)?;
     println!("part 2:");
-    println!("  a: {}  b: {}  c: {}", a, b, c);
-    println!("  a + b + c = {}", a + b + c);
-    println!("  a * b * c = {}", a * b * c);
+    println!("  a: {}  b: {}  c: {}", answer.values[0], answer.values[1], answer.values[2]);
+    println!("  a + b + c = {}", answer.sum);
+    println!("  a * b * c = {}", answer.product);
 
     Ok(())
 }
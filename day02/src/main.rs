@@ -1,24 +1,225 @@
+use parser::*;
 use std::fmt::Debug;
+use std::io::{BufRead, IsTerminal};
 use std::ops::RangeInclusive;
 
+/// How many malformed lines to show when reporting parse failures
+const MAX_REPORTED_ERRORS: usize = 5;
+
 fn main() -> anyhow::Result<()> {
-    let count1 = include_str!("input.txt")
-        .lines()
-        .map(parse_line1)
-        .map(Result::unwrap)
-        .filter(|(policy, password)| policy.is_valid(password))
-        .count();
+    // `--char-positions` counts part 2's positions in characters rather than bytes, see `Positions`
+    let parse_line2: ParseLine2 = if std::env::args().any(|arg| arg == "--char-positions") {
+        parse_line2_chars
+    } else {
+        parse_line2
+    };
+
+    // `day02 -` (or piping something in) validates passwords from stdin instead of input.txt
+    if std::env::args().any(|arg| arg == "-") || !std::io::stdin().is_terminal() {
+        return run_streaming(std::io::stdin().lock(), parse_line2);
+    }
+
+    let input = include_str!("input.txt");
+
+    // each part parses lines its own way (a count can be 0, a position can't), so each reports its own failures
+    let (entries1, errors1) = parse_lines(input, parse_line1);
+    let (entries2, errors2) = parse_lines(input, parse_line2);
+    report_errors(1, errors1.len(), &errors1);
+    report_errors(2, errors2.len(), &errors2);
+    if entries1.is_empty() && entries2.is_empty() && !errors1.is_empty() {
+        anyhow::bail!("all {} lines failed to parse", errors1.len());
+    }
+
+    let explain = std::env::args().any(|arg| arg == "--explain");
+
+    #[cfg(feature = "parallel")]
+    let (count1, count2) = count_valid_parallel(input, parse_line2);
+    #[cfg(not(feature = "parallel"))]
+    let (count1, count2) = (
+        count_valid(input.lines(), parse_line1),
+        count_valid(input.lines(), parse_line2),
+    );
+
     println!("Part 1:");
     println!("  {} passwords are valid", count1);
+    if explain {
+        explain_violations(input.lines(), parse_line1);
+    }
 
-    let count2 = include_str!("input.txt")
-        .lines()
-        .map(parse_line2)
-        .map(Result::unwrap)
-        .filter(|(policy, password)| policy.is_valid(password))
-        .count();
     println!("Part 2:");
     println!("  {} passwords are valid", count2);
+    if explain {
+        explain_violations(input.lines(), parse_line2);
+    }
+
+    Ok(())
+}
+
+trait PasswordPolicy {
+    fn validate(&self, password: &str) -> Result<(), PolicyViolation>;
+
+    fn is_valid(&self, password: &str) -> bool {
+        self.validate(password).is_ok()
+    }
+}
+
+/// Why a password doesn't satisfy its policy. Positions are 0-based, like in `PasswordPolicy2`,
+/// but are displayed 1-based to match the input.
+#[derive(thiserror::Error, Debug, PartialEq)]
+enum PolicyViolation {
+    #[error("'{}' occurs {count} times, expected {} to {}", char::from(*.byte), .range.start(), .range.end())]
+    Count {
+        byte: u8,
+        count: usize,
+        range: RangeInclusive<usize>,
+    },
+    #[error("'{}' is at neither position {} nor {}", char::from(*.byte), .positions[0] + 1, .positions[1] + 1)]
+    NeitherPosition { byte: u8, positions: [usize; 2] },
+    #[error("'{}' is at both positions {} and {}", char::from(*.byte), .positions[0] + 1, .positions[1] + 1)]
+    BothPositions { byte: u8, positions: [usize; 2] },
+}
+
+/// Prints a one-line explanation for every password that doesn't satisfy its policy
+fn explain_violations<'a, P: PasswordPolicy>(
+    lines: impl Iterator<Item = &'a str>,
+    parse: fn(&'a str) -> anyhow::Result<(P, &'a str)>,
+) {
+    for (index, line) in lines.enumerate() {
+        if let Ok((policy, password)) = parse(line) {
+            if let Err(violation) = policy.validate(password) {
+                println!("  line {}: {}: {}", index + 1, password, violation);
+            }
+        }
+    }
+}
+
+/// Counts the lines whose password satisfies the policy they were parsed with.
+/// Lines that fail to parse are skipped; use `parse_lines` to find out which ones those are.
+#[cfg_attr(feature = "parallel", allow(dead_code))]
+fn count_valid<'a, P: PasswordPolicy>(
+    lines: impl Iterator<Item = &'a str>,
+    parse: fn(&'a str) -> anyhow::Result<(P, &'a str)>,
+) -> usize {
+    lines.filter(|line| is_valid_line(line, parse)).count()
+}
+
+/// Whether `line` parses with `parse` and its password satisfies the resulting policy
+fn is_valid_line<'a, P: PasswordPolicy>(line: &'a str, parse: fn(&'a str) -> anyhow::Result<(P, &'a str)>) -> bool {
+    parse(line).is_ok_and(|(policy, password)| policy.is_valid(password))
+}
+
+/// Parses every line of `input`, keeping the entries that parsed successfully apart from the
+/// `(line_number, error)` pairs of those that didn't. Line numbers are 1-based.
+fn parse_lines<'a, T>(input: &'a str, parse: fn(&'a str) -> anyhow::Result<T>) -> (Vec<T>, Vec<(usize, anyhow::Error)>) {
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        match parse(line) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => errors.push((index + 1, err)),
+        }
+    }
+    (entries, errors)
+}
+
+/// Prints how many lines failed to parse for `part`, along with the first few of `errors`
+fn report_errors(part: usize, total: usize, errors: &[(usize, anyhow::Error)]) {
+    if total == 0 {
+        return;
+    }
+    println!("{} lines failed to parse for part {}:", total, part);
+    for (line_number, err) in errors.iter().take(MAX_REPORTED_ERRORS) {
+        println!("  line {}: {}", line_number, err);
+    }
+    if total > MAX_REPORTED_ERRORS {
+        println!("  ...and {} more", total - MAX_REPORTED_ERRORS);
+    }
+}
+
+/// Counts the valid passwords for both parts in a single pass, spreading the lines over rayon's
+/// thread pool. Lines that fail to parse for a part are skipped for that part only, as in `count_valid`.
+#[cfg(feature = "parallel")]
+fn count_valid_parallel(input: &str, parse_line2: ParseLine2) -> (usize, usize) {
+    use rayon::prelude::*;
+
+    let lines: Vec<&str> = input.lines().collect();
+    lines
+        .par_iter()
+        .map(|line| {
+            (
+                is_valid_line(line, parse_line1) as usize,
+                is_valid_line(line, parse_line2) as usize,
+            )
+        })
+        .reduce(|| (0, 0), |(a1, a2), (b1, b2)| (a1 + b1, a2 + b2))
+}
+
+// --- streaming
+
+/// Running totals for one part, gathered while validating passwords one line at a time
+#[derive(Debug, Default)]
+struct PartTotals {
+    valid: usize,
+    parsed: usize,
+    malformed: usize,
+    /// The first few lines that failed to parse for this part, as `(line_number, error)` pairs
+    errors: Vec<(usize, anyhow::Error)>,
+}
+
+impl PartTotals {
+    fn add<'a, P: PasswordPolicy>(
+        &mut self,
+        line_number: usize,
+        line: &'a str,
+        parse: fn(&'a str) -> anyhow::Result<(P, &'a str)>,
+    ) {
+        match parse(line) {
+            Ok((policy, password)) => {
+                self.parsed += 1;
+                self.valid += policy.is_valid(password) as usize;
+            }
+            Err(err) => {
+                self.malformed += 1;
+                if self.errors.len() < MAX_REPORTED_ERRORS {
+                    self.errors.push((line_number, err));
+                }
+            }
+        }
+    }
+}
+
+/// The totals for both parts; each part parses every line its own way, so a line can be malformed
+/// for one and still count for the other
+#[derive(Debug, Default)]
+struct StreamTotals {
+    part1: PartTotals,
+    part2: PartTotals,
+}
+
+/// Validates each line from `reader` against both policies as it is read, so the input is never
+/// held in memory all at once. Malformed lines are counted rather than aborting the run.
+fn count_valid_streaming(reader: impl BufRead, parse_line2: ParseLine2) -> std::io::Result<StreamTotals> {
+    let mut totals = StreamTotals::default();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        totals.part1.add(index + 1, &line, parse_line1);
+        totals.part2.add(index + 1, &line, parse_line2);
+    }
+    Ok(totals)
+}
+
+fn run_streaming(reader: impl BufRead, parse_line2: ParseLine2) -> anyhow::Result<()> {
+    let totals = count_valid_streaming(reader, parse_line2)?;
+    report_errors(1, totals.part1.malformed, &totals.part1.errors);
+    report_errors(2, totals.part2.malformed, &totals.part2.errors);
+    if totals.part1.parsed == 0 && totals.part2.parsed == 0 && totals.part1.malformed > 0 {
+        anyhow::bail!("all {} lines failed to parse", totals.part1.malformed);
+    }
+
+    println!("Part 1:");
+    println!("  {} passwords are valid", totals.part1.valid);
+    println!("Part 2:");
+    println!("  {} passwords are valid", totals.part2.valid);
 
     Ok(())
 }
@@ -46,22 +247,28 @@ impl Debug for PasswordPolicy1 {
     }
 }
 
-impl PasswordPolicy1 {
-    fn is_valid(&self, password: &str) -> bool {
+impl PasswordPolicy for PasswordPolicy1 {
+    fn validate(&self, password: &str) -> Result<(), PolicyViolation> {
         // why .copied() ... password.as_bytes().iter() gives us an Iterator<Item = &u8>
         // u8 implements the Copy trait, so we don't need to worry about its ownership
         // iter.filter() when iter is an Iterator<Item = T>, passes &T.
         // we're filtering, avoid "consuming" the items... just read and decide on inclusion
         // filter(|&b| b == self.byte) ... is equivalent to ... filter(|b| *b == self.byte)
-        self.range
-            .contains(
-                &password
-                    .as_bytes()
-                    .iter()
-                    .copied()
-                    .filter(|&b| b == self.byte)
-                    .count(),
-            )
+        let count = password
+            .as_bytes()
+            .iter()
+            .copied()
+            .filter(|&b| b == self.byte)
+            .count();
+        if self.range.contains(&count) {
+            Ok(())
+        } else {
+            Err(PolicyViolation::Count {
+                byte: self.byte,
+                count,
+                range: self.range.clone(),
+            })
+        }
     }
 }
 
@@ -69,76 +276,106 @@ impl PasswordPolicy1 {
 struct PasswordPolicy2 {
     byte: u8,
     positions: [usize; 2],
+    counting: Positions,
+}
+
+/// What `PasswordPolicy2`'s positions count. The puzzle input is ASCII only, so either gives the
+/// same answer there; they differ once a password has a multi-byte character such as 'é' before
+/// the positions checked.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Positions {
+    /// Byte offsets into the password, the default
+    Bytes,
+    /// Characters, so 'é' only occupies a single position
+    Chars,
 }
 
 impl PasswordPolicy2 {
-    fn is_valid(&self, password: &str) -> bool {
-        self.positions
-            .iter()
-            .copied()
-            .filter(|&index| password.as_bytes()[index] == self.byte)
-            .count()
-            == 1
+    /// Whether the password holds the policy's byte at `index`, counted as `self.counting` says.
+    /// A position past the end of the password never holds the byte.
+    fn has_byte_at(&self, password: &str, index: usize) -> bool {
+        match self.counting {
+            Positions::Bytes => password.as_bytes().get(index) == Some(&self.byte),
+            Positions::Chars => password.chars().nth(index) == Some(self.byte as char),
+        }
     }
 }
 
-fn parse_line1(s: &str) -> anyhow::Result<(PasswordPolicy1, &str)> {
-    peg::parser! {
-        grammar parser() for str {
-            rule number() -> usize
-                = n:$(['0'..='9']+) { n.parse().unwrap() }
-
-            rule range() -> RangeInclusive<usize>
-                = min:number() "-" max:number() { min..=max }
-
-            rule byte() -> u8
-                = letter:$(['a'..='z']) { letter.as_bytes()[0] }
-
-            rule password() -> &'input str
-                = letters:$([_]*) { letters }
-
-            pub(crate) rule line() -> (PasswordPolicy1, &'input str)
-                = range:range() " " byte:byte() ": " password:password() {
-                    (PasswordPolicy1 { range, byte }, password)
-            }
+impl PasswordPolicy for PasswordPolicy2 {
+    fn validate(&self, password: &str) -> Result<(), PolicyViolation> {
+        let [first, second] = self.positions.map(|index| self.has_byte_at(password, index));
+        match (first, second) {
+            (true, false) | (false, true) => Ok(()),
+            (false, false) => Err(PolicyViolation::NeitherPosition {
+                byte: self.byte,
+                positions: self.positions,
+            }),
+            (true, true) => Err(PolicyViolation::BothPositions {
+                byte: self.byte,
+                positions: self.positions,
+            }),
         }
     }
-
-    Ok(parser::line(s)?)
 }
 
-fn parse_line2(s: &str) -> anyhow::Result<(PasswordPolicy2, &str)> {
-    peg::parser! {
-        grammar parser() for str {
-            rule number() -> usize
-                = n:$(['0'..='9']+) { n.parse().unwrap() }
+// --- parser
 
-            // Positions are 1-based indices in the input
-            rule position() -> usize
-                = n:number() { n - 1 }
+fn range_inclusive<'a>() -> impl Parser<'a, RangeInclusive<usize>> {
+    pair(left(integer, match_literal("-")), integer, |min, max| {
+        (min as usize)..=(max as usize)
+    })
+}
 
-            rule positions() -> [usize; 2]
-                // now using `position()` rather than `number()` (giving 0-based values)
-                = first:position() "-" second:position() { [first, second] }
+fn policy_byte<'a>() -> impl Parser<'a, u8> {
+    any_char.pred(|c| c.is_ascii_lowercase()).map(|c| c as u8)
+}
 
-            rule byte() -> u8
-                = letter:$(['a'..='z']) { letter.as_bytes()[0] }
+/// Runs `policy` over the start of the line; everything after the ": " that follows it is the password
+fn parse_policy_line<'a, P>(s: &'a str, policy: impl Parser<'a, P>) -> anyhow::Result<(P, &'a str)> {
+    let (password, policy) = left(policy, match_literal(": "))
+        .parse(s)
+        .map_err(|rest| anyhow::anyhow!("unexpected input at {:?}", rest))?;
+    Ok((policy, password))
+}
 
-            rule password() -> &'input str
-                = letters:$([_]*) { letters }
+fn parse_line1(s: &str) -> anyhow::Result<(PasswordPolicy1, &str)> {
+    let policy = pair(left(range_inclusive(), match_literal(" ")), policy_byte(), |range, byte| {
+        PasswordPolicy1 { range, byte }
+    });
+    parse_policy_line(s, policy)
+}
 
-            pub(crate) rule line() -> (PasswordPolicy2, &'input str)
-                = positions:positions() " " byte:byte() ": " password:password() {
-                    (PasswordPolicy2 { positions, byte }, password)
-            }
+/// `parse_line2` or `parse_line2_chars`, whichever `main` was told to use
+type ParseLine2 = fn(&str) -> anyhow::Result<(PasswordPolicy2, &str)>;
+
+fn parse_line2(s: &str) -> anyhow::Result<(PasswordPolicy2, &str)> {
+    // Positions are 1-based indices in the input, we want 0-based values
+    let positions = range_inclusive()
+        .pred(|range| *range.start() >= 1 && *range.end() >= 1)
+        .map(|range| [*range.start() - 1, *range.end() - 1]);
+    let policy = pair(left(positions, match_literal(" ")), policy_byte(), |positions, byte| {
+        PasswordPolicy2 {
+            positions,
+            byte,
+            counting: Positions::Bytes,
         }
-    }
+    });
+    parse_policy_line(s, policy)
+}
 
-    Ok(parser::line(s)?)
+/// Like `parse_line2`, but the positions count characters rather than bytes
+fn parse_line2_chars(s: &str) -> anyhow::Result<(PasswordPolicy2, &str)> {
+    let (policy, password) = parse_line2(s)?;
+    let policy = PasswordPolicy2 {
+        counting: Positions::Chars,
+        ..policy
+    };
+    Ok((policy, password))
 }
 
 #[cfg(test)]
 mod tests {
+    use super::PasswordPolicy;
     use super::PasswordPolicy1;
 
     #[test]
@@ -167,6 +404,7 @@ mod tests {
                 "banana"
             )
         );
+        assert!(parse_line1("99999999999999999999-3 a: aaa").is_err(), "too big for an integer");
 
         /*
         // these checks only apply to our manual parser's error messages
@@ -181,13 +419,14 @@ mod tests {
         */
     }
 
-    use super::PasswordPolicy2;
+    use super::{parse_line2_chars, PasswordPolicy2, Positions};
 
     #[test]
     fn test_is_valid2() {
         let pp = PasswordPolicy2 {
             positions: [0, 2], // now 0-based
             byte: b'a',
+            counting: Positions::Bytes,
         };
         assert!(pp.is_valid("abcde"), "'a' in position 1");
         assert!(pp.is_valid("bcade"), "'a' in position 3");
@@ -195,6 +434,41 @@ mod tests {
         assert!(!pp.is_valid("abacus"), "'a' in both positions");
     }
 
+    #[test]
+    fn test_is_valid2_short_password() {
+        for counting in [Positions::Bytes, Positions::Chars] {
+            let pp = PasswordPolicy2 {
+                positions: [8, 11],
+                byte: b'x',
+                counting,
+            };
+            assert!(!pp.is_valid("xxxxx"), "both positions past the end");
+            assert!(pp.is_valid("abcdefghx"), "second position past the end");
+        }
+    }
+
+    #[test]
+    fn test_is_valid2_non_ascii() {
+        let pp = PasswordPolicy2 {
+            positions: [0, 1],
+            byte: b'a',
+            counting: Positions::Bytes,
+        };
+        // 'é' is two bytes long, so the 'a' is at byte 2 but at char 1
+        assert!(!pp.is_valid("éa"), "'a' past both byte positions");
+        let pp = PasswordPolicy2 {
+            counting: Positions::Chars,
+            ..pp
+        };
+        assert!(pp.is_valid("éa"), "'a' in char position 2");
+
+        let (policy, password) = parse_line2_chars("1-2 a: éa").unwrap();
+        assert_eq!(policy.counting, Positions::Chars);
+        assert!(policy.is_valid(password));
+        assert_eq!(count_valid(["1-2 a: éa"].into_iter(), parse_line2), 0);
+        assert_eq!(count_valid(["1-2 a: éa"].into_iter(), parse_line2_chars), 1);
+    }
+
     use super::parse_line2;
 
     #[test]
@@ -205,10 +479,179 @@ mod tests {
                 PasswordPolicy2 {
                     positions: [0, 2], // now 0-based
                     byte: b'a',
+                    counting: Positions::Bytes,
                 },
                 "banana"
             )
         );
+        assert!(parse_line2("0-3 a: banana").is_err(), "positions are 1-based");
+        assert!(parse_line2("1-99999999999999999999 a: aaa").is_err(), "too big for an integer");
+        assert!(parse_line2("1-0 a: banana").is_err(), "positions are 1-based");
+    }
+
+    #[test]
+    fn test_parse_password_with_spaces() {
+        assert_eq!(
+            parse_line1("1-3 a: ban ana").unwrap(),
+            (
+                PasswordPolicy1 {
+                    range: 1..=3,
+                    byte: b'a',
+                },
+                "ban ana"
+            )
+        );
+        assert_eq!(parse_line2("1-3 a: a b").unwrap().1, "a b");
+    }
+
+    use super::parse_lines;
+
+    #[test]
+    fn test_parse_lines_with_corrupted_line() {
+        let (entries, errors) = parse_lines(include_str!("corrupted.txt"), parse_line1);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, 3, "corrupted line is reported by its 1-based number");
+        assert_eq!(
+            entries
+                .iter()
+                .filter(|(policy, password)| policy.is_valid(password))
+                .count(),
+            2
+        );
+
+        // position 0 is only malformed for part 2
+        let input = "0-3 a: aaa\n1-3 a: abc";
+        assert!(parse_lines(input, parse_line1).1.is_empty());
+        let (entries, errors) = parse_lines(input, parse_line2);
+        assert_eq!((entries.len(), errors.len()), (1, 1));
+        assert_eq!(errors[0].0, 1);
+    }
+
+    use super::PolicyViolation;
+
+    #[test]
+    fn test_validate_puzzle_examples() {
+        let (policy, password) = parse_line1("1-3 b: cdefg").unwrap();
+        let violation = policy.validate(password).unwrap_err();
+        assert_eq!(
+            violation,
+            PolicyViolation::Count {
+                byte: b'b',
+                count: 0,
+                range: 1..=3
+            }
+        );
+        assert_eq!(violation.to_string(), "'b' occurs 0 times, expected 1 to 3");
+        let (policy, password) = parse_line1("1-3 a: abcde").unwrap();
+        assert_eq!(policy.validate(password), Ok(()));
+
+        let (policy, password) = parse_line2("1-3 b: cdefg").unwrap();
+        let violation = policy.validate(password).unwrap_err();
+        assert_eq!(
+            violation,
+            PolicyViolation::NeitherPosition {
+                byte: b'b',
+                positions: [0, 2]
+            }
+        );
+        assert_eq!(violation.to_string(), "'b' is at neither position 1 nor 3");
+
+        let (policy, password) = parse_line2("2-9 c: ccccccccc").unwrap();
+        let violation = policy.validate(password).unwrap_err();
+        assert_eq!(
+            violation,
+            PolicyViolation::BothPositions {
+                byte: b'c',
+                positions: [1, 8]
+            }
+        );
+        assert_eq!(violation.to_string(), "'c' is at both positions 2 and 9");
+    }
+
+    use super::count_valid_streaming;
+
+    #[test]
+    fn test_count_valid_streaming() {
+        let totals = count_valid_streaming(include_str!("sample.txt").as_bytes(), parse_line2).unwrap();
+        assert_eq!((totals.part1.valid, totals.part2.valid), (2, 1));
+        assert_eq!((totals.part1.parsed, totals.part1.malformed), (3, 0));
+
+        let totals = count_valid_streaming(include_str!("corrupted.txt").as_bytes(), parse_line2).unwrap();
+        assert_eq!((totals.part1.valid, totals.part2.valid), (2, 1));
+        assert_eq!((totals.part1.parsed, totals.part1.malformed), (3, 1));
+        assert_eq!(totals.part1.errors[0].0, 3);
+        assert_eq!((totals.part2.parsed, totals.part2.malformed), (3, 1));
+
+        // a count can be 0, but a position can't, so the first line only counts for part 1
+        let totals = count_valid_streaming("0-3 a: aaa\n1-3 a: abc\n".as_bytes(), parse_line2).unwrap();
+        assert_eq!((totals.part1.valid, totals.part2.valid), (2, 1));
+        assert_eq!((totals.part1.parsed, totals.part1.malformed), (2, 0));
+        assert_eq!((totals.part2.parsed, totals.part2.malformed), (1, 1));
+        assert_eq!(totals.part2.errors[0].0, 1);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_count_valid_parallel() {
+        use rand_xoshiro::rand_core::{RngCore, SeedableRng};
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // a seeded generator keeps the synthetic input the same from run to run
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(2020);
+        let mut next = move |n: u64| rng.next_u64() % n;
+        let lines: Vec<String> = (0..10_000)
+            .map(|_| {
+                // a 0 is fine as a part 1 count, but isn't a part 2 position
+                let lo = next(5);
+                let hi = lo + next(5);
+                let byte = (b'a' + next(3) as u8) as char;
+                let password: String = (0..next(12) + 1).map(|_| (b'a' + next(3) as u8) as char).collect();
+                format!("{}-{} {}: {}", lo, hi, byte, password)
+            })
+            .collect();
+        let input = lines.join("\n");
+        assert!(input.lines().any(|line| line.starts_with("0-")));
+
+        assert_eq!(
+            super::count_valid_parallel(&input, parse_line2),
+            (
+                count_valid(input.lines(), parse_line1),
+                count_valid(input.lines(), parse_line2)
+            )
+        );
+        assert_eq!(super::count_valid_parallel("0-3 a: aaa\n1-3 a: abc", parse_line2), (2, 1));
+    }
+
+    use super::count_valid;
+
+    /// Accepts any password that doesn't contain an 'x'
+    struct NoX;
+
+    impl PasswordPolicy for NoX {
+        fn validate(&self, password: &str) -> Result<(), PolicyViolation> {
+            match password.matches('x').count() {
+                0 => Ok(()),
+                count => Err(PolicyViolation::Count {
+                    byte: b'x',
+                    count,
+                    range: 0..=0,
+                }),
+            }
+        }
+    }
+
+    fn parse_no_x(s: &str) -> anyhow::Result<(NoX, &str)> {
+        Ok((NoX, s))
+    }
+
+    #[test]
+    fn test_count_valid() {
+        let lines = ["ab", "xyz", "abcd", ""];
+        assert_eq!(count_valid(lines.into_iter(), parse_no_x), 3);
+        assert_eq!(count_valid(include_str!("sample.txt").lines(), parse_line1), 2);
+        assert_eq!(count_valid(include_str!("sample.txt").lines(), parse_line2), 1);
+        assert_eq!(count_valid(include_str!("corrupted.txt").lines(), parse_line1), 2);
     }
 }
 
@@ -247,7 +690,7 @@ fn parse_line0(s: &str) -> anyhow::Result<(PasswordPolicy1, &str)> {
         )
     };
 
-    let byte = if byte.as_bytes().len() == 1 {
+    let byte = if byte.len() == 1 {
         byte.as_bytes()[0]
     } else {
         return Err(ParseError::Expected("password policy byte to be exactly 1 byte").into());
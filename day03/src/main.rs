@@ -1,8 +1,10 @@
+use std::collections::HashSet;
 use std::fmt;
-use std::ops::AddAssign;
+use std::str::FromStr;
+use std::ops::{Add, AddAssign};
 
 // Vec2 will be used to represent positions on the map
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Vec2 {
     x: i64,
     y: i64,
@@ -14,6 +16,17 @@ impl From<(i64, i64)> for Vec2 {
     }
 }
 
+impl Add for Vec2 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
 impl AddAssign for Vec2 {
     fn add_assign(&mut self, rhs: Self) {
         self.x += rhs.x;
@@ -22,75 +35,126 @@ impl AddAssign for Vec2 {
 }
 
 // Tile will represent what's _in_ a tile.
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Default)]
 enum Tile {
+    #[default]
     Open,
     Tree,
 }
 
-impl Default for Tile {
-    fn default() -> Self {
-        Self::Open
+impl Tile {
+    fn glyph(self) -> char {
+        match self {
+            Tile::Open => '.',
+            Tile::Tree => '#',
+        }
     }
 }
 
 impl fmt::Debug for Tile {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let c = match self {
-            Tile::Open => '.',
-            Tile::Tree => '#',
-        };
-        write!(f, "{}", c)
+        write!(f, "{}", self.glyph())
     }
 }
 
+/// WrapMode decides which edges of the map wrap around to the opposite side
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum WrapMode {
+    /// The map repeats to the left and right, but has a top and a bottom
+    #[default]
+    Cylinder,
+    /// The map also repeats above and below, so there's no falling off it
+    Torus,
+}
+
 struct Map {
     size: Vec2,
+    wrap: WrapMode,
     tiles: Vec<Tile>,
 }
 
-// We're storing all tiles in a flat array, in row-major order, which means we're
-// storing all tiles from the top row first, then we move on to the second row, etc.
-impl Map {
-    fn new(size: Vec2) -> Self {
-        let num_tiles = size.x * size.y;
-        Self {
-            size,
-            tiles: (0..num_tiles)
-                .into_iter()
-                .map(|_| Default::default())
-                .collect(),
-        }
-    }
+/// Grid is what a map needs to provide so we can walk slopes across it, whichever way its
+/// tiles are stored. Positions wrap around horizontally, see `normalize_pos()`.
+trait Grid {
+    fn size(&self) -> Vec2;
+
+    fn wrap(&self) -> WrapMode;
+
+    // get() gives back the Tile for a given pos. We simplify get() by returning a Tile
+    // instead of Option<Tile>. Tiles outside the map are open.
+    fn get(&self, pos: Vec2) -> Tile;
+
+    // set() allows us to assign a Tile value to a particular pos. We simplify set() by
+    // assuming that every tile outside the map is immutable.
+    fn set(&mut self, pos: Vec2, tile: Tile);
 
     /// normalize_pos() wraps the x coordinate so the map extends infinitely to the left and right.
-    /// Map has finite height. Returns `None` for coordinates above 0 or below `self.size.y
+    /// A cylinder map has finite height: returns `None` for coordinates above 0 or below
+    /// `self.size.y`. A torus map wraps the y coordinate the same way as x.
     fn normalize_pos(&self, pos: Vec2) -> Option<Vec2> {
-        if pos.y < 0 || pos.y >= self.size.y {
-            None
-        } else {
-            let x = pos.x % self.size.x;
-            // wrap around for left side (negative X coordinates)
-            let x = if x < 0 { self.size.x + x } else { x };
-            Some((x, pos.y).into())
-        }
+        let size = self.size();
+        let y = match self.wrap() {
+            WrapMode::Cylinder if pos.y < 0 || pos.y >= size.y => return None,
+            WrapMode::Cylinder => pos.y,
+            WrapMode::Torus => pos.y.rem_euclid(size.y),
+        };
+        let x = pos.x % size.x;
+        // wrap around for left side (negative X coordinates)
+        let x = if x < 0 { size.x + x } else { x };
+        Some((x, y).into())
     }
 
     // index() returns the index of a tile in our flat storage
     // None is returned for positions that do not exist on the map (above or below it)
     fn index(&self, pos: Vec2) -> Option<usize> {
         self.normalize_pos(pos)
-            .map(|pos| (pos.x + pos.y * self.size.x) as _)
+            .map(|pos| (pos.x + pos.y * self.size().x) as _)
+    }
+
+    /// itinerary_iter() lazily produces the positions visited from a given moving pattern,
+    /// stopping once we've gone past the bottom (or top) of the map. On a torus we never leave
+    /// the map, so we stop just before the path gets back to the origin instead.
+    fn itinerary_iter(&self, delta: Vec2) -> impl Iterator<Item = Vec2> + '_ {
+        let max_steps = match self.wrap() {
+            WrapMode::Cylinder => usize::MAX,
+            WrapMode::Torus => torus_period(self.size(), delta),
+        };
+        std::iter::successors(Some(Vec2::from((0, 0))), move |&pos| Some(pos + delta))
+            .take_while(move |&pos| self.normalize_pos(pos).is_some())
+            .take(max_steps)
+    }
+
+    /// trees_hit() gives the (normalized) positions of the trees hit along a moving pattern
+    fn trees_hit(&self, delta: Vec2) -> Vec<Vec2> {
+        self.itinerary_iter(delta)
+            .filter(|&pos| self.get(pos) == Tile::Tree)
+            .filter_map(|pos| self.normalize_pos(pos))
+            .collect()
+    }
+
+    /// count_trees() counts the trees hit along a moving pattern, without collecting the itinerary
+    fn count_trees(&self, delta: Vec2) -> usize {
+        self.itinerary_iter(delta)
+            .filter(|&pos| self.get(pos) == Tile::Tree)
+            .count()
+    }
+}
+
+// We're storing all tiles in a flat array, in row-major order, which means we're
+// storing all tiles from the top row first, then we move on to the second row, etc.
+impl Grid for Map {
+    fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    fn wrap(&self) -> WrapMode {
+        self.wrap
     }
 
-    // get() gives back the Tile for a given pos. We simplify get() by returning a Tile
-    // instead of Option<Tile>. Tiles outside the map are open.
     fn get(&self, pos: Vec2) -> Tile {
         self.index(pos).map(|i| self.tiles[i]).unwrap_or_default()
     }
 
-    // set() allows us to assign a Tile value to a particular pos. We simplify set() by
-    // assuming that every tile outside the map is immutable.
     fn set(&mut self, pos: Vec2, tile: Tile) {
         // you can think of if let as syntax sugar for a match that runs code
         // when the value matches one pattern and then ignores other values
@@ -98,34 +162,140 @@ impl Map {
             self.tiles[index] = tile
         }
     }
+}
+
+impl Map {
+    fn new(size: Vec2) -> Self {
+        let num_tiles = size.x * size.y;
+        Self {
+            size,
+            wrap: WrapMode::Cylinder,
+            tiles: (0..num_tiles).map(|_| Default::default()).collect(),
+        }
+    }
+
+    fn with_wrap(self, wrap: WrapMode) -> Self {
+        Self { wrap, ..self }
+    }
 
     // input comes from include_bytes! working with input.txt
-    fn parse(input: &[u8]) -> Self {
-        let mut columns = 0;
-        let mut rows = 1;
-        for &c in input.iter() {
-            if c == b'\n' {
-                rows += 1;
-                columns = 0;
-            } else {
-                columns += 1;
+    fn parse(input: &[u8]) -> Result<Self, MapParseError> {
+        // a trailing newline ends the last row, it doesn't start a new one
+        let input = input.strip_suffix(b"\n").unwrap_or(input);
+        if input.is_empty() {
+            return Err(MapParseError::Empty);
+        }
+
+        let lines: Vec<&[u8]> = input.split(|&c| c == b'\n').collect();
+        let columns = lines[0].len();
+        if columns == 0 {
+            // positions wrap around modulo the width, so there has to be at least one column
+            return Err(MapParseError::NoColumns);
+        }
+        let mut map = Self::new((columns as i64, lines.len() as i64).into());
+        for (row, line) in lines.iter().enumerate() {
+            if line.len() != columns {
+                return Err(MapParseError::RaggedRow {
+                    row,
+                    expected: columns,
+                    found: line.len(),
+                });
+            }
+            for (col, &byte) in line.iter().enumerate() {
+                let tile = match byte {
+                    b'.' => Tile::Open,
+                    b'#' => Tile::Tree,
+                    byte => return Err(MapParseError::UnexpectedByte { row, col, byte }),
+                };
+                map.set((col as i64, row as i64).into(), tile);
             }
         }
+        Ok(map)
+    }
+}
+
+/// torus_period() is how many steps it takes to get back to where we started on a torus:
+/// each axis repeats on its own, and both have to line up again.
+fn torus_period(size: Vec2, delta: Vec2) -> usize {
+    fn gcd(a: i64, b: i64) -> i64 {
+        if b == 0 {
+            a
+        } else {
+            gcd(b, a % b)
+        }
+    }
+    let steps_x = size.x / gcd(size.x, delta.x.rem_euclid(size.x));
+    let steps_y = size.y / gcd(size.y, delta.y.rem_euclid(size.y));
+    (steps_x / gcd(steps_x, steps_y) * steps_y) as usize
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+enum MapParseError {
+    #[error("map is empty")]
+    Empty,
+    #[error("map rows have no tiles")]
+    NoColumns,
+    #[error("row {row} is {found} tiles wide, expected {expected} like the first row")]
+    RaggedRow { row: usize, expected: usize, found: usize },
+    #[error("expected '.' or '#' at row {row}, column {col}, but got {:?}", char::from(*.byte))]
+    UnexpectedByte { row: usize, col: usize, byte: u8 },
+}
+
+/// BitMap stores the same tiles as Map, but packs them into u64 words, one bit per tile
+/// (set for trees). That's an 8th of the memory of one Tile per byte.
+struct BitMap {
+    size: Vec2,
+    wrap: WrapMode,
+    words: Vec<u64>,
+}
+
+impl BitMap {
+    fn new(size: Vec2) -> Self {
+        let num_tiles = (size.x * size.y) as usize;
+        Self {
+            size,
+            wrap: WrapMode::Cylinder,
+            words: vec![0; num_tiles.div_ceil(64)],
+        }
+    }
+}
+
+impl Grid for BitMap {
+    fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    fn wrap(&self) -> WrapMode {
+        self.wrap
+    }
 
-        let mut iter = input.iter().copied();
-        let mut map = Self::new((columns, rows).into());
+    fn get(&self, pos: Vec2) -> Tile {
+        match self.index(pos) {
+            Some(i) if self.words[i / 64] & (1 << (i % 64)) != 0 => Tile::Tree,
+            _ => Tile::Open,
+        }
+    }
+
+    fn set(&mut self, pos: Vec2, tile: Tile) {
+        if let Some(i) = self.index(pos) {
+            match tile {
+                Tile::Tree => self.words[i / 64] |= 1 << (i % 64),
+                Tile::Open => self.words[i / 64] &= !(1 << (i % 64)),
+            }
+        }
+    }
+}
+
+impl From<&Map> for BitMap {
+    fn from(map: &Map) -> Self {
+        let mut bitmap = Self::new(map.size);
+        bitmap.wrap = map.wrap;
         for row in 0..map.size.y {
             for col in 0..map.size.x {
-                let tile = match iter.next() {
-                    Some(b'.') => Tile::Open,
-                    Some(b'#') => Tile::Tree,
-                    c => panic!("Expected '.' or '#', but got: {:?}", c),
-                };
-                map.set((col, row).into(), tile);
+                bitmap.set((col, row).into(), map.get((col, row).into()));
             }
-            iter.next();
         }
-        map
+        bitmap
     }
 }
 
@@ -141,6 +311,59 @@ impl fmt::Debug for Map {
     }
 }
 
+// Display renders the map like the puzzle input: rows separated by newlines, without a
+// trailing one, so that the output can be parsed back into the same map.
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_grid(f, |_, tile| tile.glyph())
+    }
+}
+
+impl Map {
+    /// write_grid() writes the map out the way Display does, asking `render` for what to draw
+    /// on each tile
+    fn write_grid(&self, w: &mut impl fmt::Write, render: impl Fn(Vec2, Tile) -> char) -> fmt::Result {
+        for row in 0..self.size.y {
+            if row > 0 {
+                writeln!(w)?;
+            }
+            for col in 0..self.size.x {
+                let pos = Vec2::from((col, row));
+                write!(w, "{}", render(pos, self.get(pos)))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// annotated() renders the map with the path of a moving pattern drawn on it, like the
+    /// puzzle's illustration: trees we hit are drawn as `X`, open squares we pass as `O`
+    fn annotated(&self, delta: Vec2) -> String {
+        let path: HashSet<Vec2> = self
+            .itinerary_iter(delta)
+            .filter_map(|pos| self.normalize_pos(pos))
+            .collect();
+        let mut out = String::new();
+        self.write_grid(&mut out, |pos, tile| match (path.contains(&pos), tile) {
+            (true, Tile::Tree) => 'X',
+            (true, Tile::Open) => 'O',
+            (false, tile) => tile.glyph(),
+        })
+        .expect("writing to a String can't fail");
+        out
+    }
+}
+
+impl FromStr for Map {
+    type Err = MapParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s.as_bytes())
+    }
+}
+
+
+/// Command line flags, everything else is a slope
+const FLAGS: &[&str] = &["--packed", "--torus", "--verbose", "--annotate"];
 
 fn main() -> anyhow::Result<()> {
     /*
@@ -156,52 +379,108 @@ fn main() -> anyhow::Result<()> {
     println!("{:?}", map);
     */
 
-    let map = Map::parse(include_bytes!("input.txt"));
-    let itinerary = (0..map.size.y).into_iter().map(|y| Vec2::from((y * 3, y)));
-    let num_trees = itinerary.filter(|&pos| map.get(pos) == Tile::Tree).count();
+    let map = Map::parse(include_bytes!("input.txt"))?;
+    // `--torus` makes the map wrap vertically as well, for experimenting with other puzzles
+    let map = if std::env::args().any(|arg| arg == "--torus") {
+        map.with_wrap(WrapMode::Torus)
+    } else {
+        map
+    };
+    // `--packed` walks the slopes over a bit-packed copy of the map instead
+    let bitmap = std::env::args().any(|arg| arg == "--packed").then(|| BitMap::from(&map));
+    let count_trees = |delta: Vec2| match &bitmap {
+        Some(bitmap) => bitmap.count_trees(delta),
+        None => map.count_trees(delta),
+    };
+
+    // `--verbose` lists where each slope hits trees, `--annotate` also draws its path on the map
+    let annotate = std::env::args().any(|arg| arg == "--annotate");
+    let verbose = annotate || std::env::args().any(|arg| arg == "--verbose");
+    let report = |delta: Vec2| {
+        if verbose {
+            let hits: Vec<String> = map
+                .trees_hit(delta)
+                .iter()
+                .map(|pos| format!("({}, {})", pos.x, pos.y))
+                .collect();
+            println!("    trees hit at: {}", hits.join(" "));
+        }
+        if annotate {
+            println!("{}", map.annotated(delta));
+        }
+    };
+
+    let num_trees = count_trees((3, 1).into());
     println!("Part 1:");
     println!("  We encountered {} trees", num_trees);
+    report((3, 1).into());
 
     // Part 2 asks that we explore a collection of different moving patterns.
     // Right 1, down 1    Right 3, down 1   Right 5, down 1    Right 7, down 1    Right 1, down 2
-    let deltas: &[Vec2] = &[
-        (1, 1).into(),
-        (3, 1).into(),
-        (5, 1).into(),
-        (7, 1).into(),
-        (1, 2).into(),
-    ];
+    // Other slopes can be given on the command line, e.g. `day03 1,1 3,1` or `day03 --slope 3,1`
+    let deltas = parse_slopes(std::env::args().skip(1).filter(|arg| !FLAGS.contains(&arg.as_str())))?;
+    let deltas = if deltas.is_empty() {
+        vec![(1, 1).into(), (3, 1).into(), (5, 1).into(), (7, 1).into(), (1, 2).into()]
+    } else {
+        deltas
+    };
+
+    println!("Part 2:");
     let answer = deltas
         .iter()
         .copied()
-        // generate all itineraries
-        .map(|delta| generate_itinerary(&map, delta))
-        // count trees
-        .map(|itin| {
-            itin.into_iter()
-                .filter(|&pos| map.get(pos) == Tile::Tree)
-                .count()
+        // count trees along every itinerary
+        .map(|delta| {
+            let num_trees = count_trees(delta);
+            println!("  Right {}, down {}: {} trees", delta.x, delta.y, num_trees);
+            report(delta);
+            num_trees
         })
         // multiply everything together
         .product::<usize>();
 
-    println!("Part 2:");
     println!("  The answer is {}", answer);
 
     Ok(())
 }
 
-/// generate_itinerary() produces a list of positions from a given moving pattern.
-/// A borrowed &Map allows us to stop once we've exceeded the map's bounds
-fn generate_itinerary(map: &Map, delta: Vec2) -> Vec<Vec2> {
-    let mut pos = Vec2::from((0,0));
-    let mut res: Vec<_> = Default::default();
+#[derive(thiserror::Error, Debug, PartialEq)]
+enum SlopeParseError {
+    #[error("expected a slope like `3,1` (right, down), but got {0:?}")]
+    Malformed(String),
+    #[error("slope {0:?} doesn't contain valid numbers")]
+    NotANumber(String),
+    #[error("slope {0:?} must move down at least one row")]
+    ZeroDown(String),
+    #[error("--slope must be followed by a slope like `3,1`")]
+    MissingSlope,
+}
+
+/// parse_slope() reads a "right,down" pair such as `3,1`
+fn parse_slope(s: &str) -> Result<Vec2, SlopeParseError> {
+    let (x, y) = s
+        .split_once(',')
+        .ok_or_else(|| SlopeParseError::Malformed(s.to_string()))?;
+    let parse = |n: &str| n.trim().parse::<i64>().map_err(|_| SlopeParseError::NotANumber(s.to_string()));
+    let delta = Vec2::from((parse(x)?, parse(y)?));
+    if delta.y == 0 {
+        return Err(SlopeParseError::ZeroDown(s.to_string()));
+    }
+    Ok(delta)
+}
 
-    while map.normalize_pos(pos).is_some() {
-        res.push(pos);
-        pos += delta;
+/// parse_slopes() accepts slopes either on their own or following a `--slope` flag
+fn parse_slopes(mut args: impl Iterator<Item = String>) -> Result<Vec<Vec2>, SlopeParseError> {
+    let mut slopes = Vec::new();
+    while let Some(arg) = args.next() {
+        let slope = if arg == "--slope" {
+            args.next().ok_or(SlopeParseError::MissingSlope)?
+        } else {
+            arg
+        };
+        slopes.push(parse_slope(&slope)?);
     }
-    res
+    Ok(slopes)
 }
 
 #[test]
@@ -233,9 +512,9 @@ fn test_index() {
 }
 
 #[test]
-fn test_generate_itinerary() {
+fn test_itinerary_iter() {
     assert_eq!(
-        &generate_itinerary(&Map::new((5, 5).into()), (1, 1).into()),
+        &Map::new((5, 5).into()).itinerary_iter((1, 1).into()).collect::<Vec<_>>(),
         &[
             (0, 0).into(),
             (1, 1).into(),
@@ -247,7 +526,7 @@ fn test_generate_itinerary() {
     );
 
     assert_eq!(
-        &generate_itinerary(&Map::new((5, 5).into()), (3, 1).into()),
+        &Map::new((5, 5).into()).itinerary_iter((3, 1).into()).collect::<Vec<_>>(),
         &[
             (0, 0).into(),
             (3, 1).into(),
@@ -259,14 +538,159 @@ fn test_generate_itinerary() {
     );
 
     assert_eq!(
-        &generate_itinerary(&Map::new((5, 5).into()), (2, 2).into()),
+        &Map::new((5, 5).into()).itinerary_iter((2, 2).into()).collect::<Vec<_>>(),
         &[(0, 0).into(), (2, 2).into(), (4, 4).into(),],
         "right 2 down 2, 5x5 map"
     );
 
     assert_eq!(
-        &generate_itinerary(&Map::new((9, 9).into()), (2, 5).into()),
+        &Map::new((9, 9).into()).itinerary_iter((2, 5).into()).collect::<Vec<_>>(),
         &[(0, 0).into(), (2, 5).into(),],
         "right 2 down 5, 9x9 map"
     );
 }
+
+#[test]
+fn test_parse() {
+    let map = Map::parse(include_bytes!("sample.txt")).unwrap();
+    assert_eq!(map.size, (11, 11).into());
+    assert_eq!(map.get((0, 0).into()), Tile::Open);
+    assert_eq!(map.get((2, 0).into()), Tile::Tree);
+    assert_eq!(map.get((0, 1).into()), Tile::Tree);
+
+    let map = Map::parse(b".#\n#.\n").unwrap();
+    assert_eq!(map.size, (2, 2).into(), "trailing newline doesn't add a row");
+    assert_eq!(map.get((1, 0).into()), Tile::Tree);
+    assert_eq!(map.get((0, 1).into()), Tile::Tree);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(Map::parse(b"").unwrap_err(), MapParseError::Empty);
+    assert_eq!(Map::parse(b"\n").unwrap_err(), MapParseError::Empty);
+    assert_eq!(Map::parse(b"\n\n\n").unwrap_err(), MapParseError::NoColumns);
+    assert_eq!(
+        Map::parse(b"..#\n.#\n#..").unwrap_err(),
+        MapParseError::RaggedRow {
+            row: 1,
+            expected: 3,
+            found: 2
+        }
+    );
+    assert_eq!(
+        Map::parse(b"..#\n.#X\n#..").unwrap_err(),
+        MapParseError::UnexpectedByte {
+            row: 1,
+            col: 2,
+            byte: b'X'
+        }
+    );
+    assert_eq!(
+        Map::parse(b"..#\n.#X").unwrap_err().to_string(),
+        "expected '.' or '#' at row 1, column 2, but got 'X'"
+    );
+}
+
+#[test]
+fn test_parse_slopes() {
+    let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>().into_iter();
+    assert_eq!(parse_slopes(args(&[])), Ok(vec![]));
+    assert_eq!(
+        parse_slopes(args(&["1,1", "--slope", "3,1", "1,2"])),
+        Ok(vec![(1, 1).into(), (3, 1).into(), (1, 2).into()])
+    );
+    assert_eq!(parse_slopes(args(&["3"])), Err(SlopeParseError::Malformed("3".into())));
+    assert_eq!(parse_slopes(args(&["3,x"])), Err(SlopeParseError::NotANumber("3,x".into())));
+    assert_eq!(parse_slopes(args(&["3,0"])), Err(SlopeParseError::ZeroDown("3,0".into())));
+    assert_eq!(parse_slopes(args(&["--slope"])), Err(SlopeParseError::MissingSlope));
+}
+
+#[test]
+fn test_count_trees() {
+    let map = Map::parse(include_bytes!("sample.txt")).unwrap();
+    let deltas: [Vec2; 5] = [(1, 1).into(), (3, 1).into(), (5, 1).into(), (7, 1).into(), (1, 2).into()];
+    let counts: Vec<_> = deltas.iter().map(|&delta| map.count_trees(delta)).collect();
+    assert_eq!(counts, vec![2, 7, 3, 4, 2]);
+
+    // the path-based count we used to compute from a collected itinerary
+    for delta in deltas {
+        let path: Vec<_> = map.itinerary_iter(delta).collect();
+        let path_count = path.into_iter().filter(|&pos| map.get(pos) == Tile::Tree).count();
+        assert_eq!(map.count_trees(delta), path_count);
+    }
+}
+
+#[test]
+fn test_bitmap() {
+    let map = Map::parse(include_bytes!("sample.txt")).unwrap();
+    let bitmap = BitMap::from(&map);
+    assert_eq!(bitmap.words.len(), 2, "121 tiles fit in two words");
+    for row in 0..map.size.y {
+        for col in 0..map.size.x {
+            assert_eq!(bitmap.get((col, row).into()), map.get((col, row).into()));
+        }
+    }
+    for delta in [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)] {
+        assert_eq!(bitmap.count_trees(delta.into()), map.count_trees(delta.into()));
+    }
+
+    let mut bitmap = BitMap::new((3, 3).into());
+    bitmap.set((-1, 2).into(), Tile::Tree);
+    assert_eq!(bitmap.get((2, 2).into()), Tile::Tree);
+    bitmap.set((2, 2).into(), Tile::Open);
+    assert_eq!(bitmap.get((2, 2).into()), Tile::Open);
+}
+
+#[test]
+fn test_display_round_trip() {
+    for s in [include_str!("sample.txt"), "#", "..#\n#..\n.#.", "....\n####"] {
+        assert_eq!(Map::from_str(s).unwrap().to_string(), s);
+    }
+    // a trailing newline is accepted, but not reproduced
+    assert_eq!(Map::from_str(".#\n#.\n").unwrap().to_string(), ".#\n#.");
+}
+
+#[test]
+fn test_torus() {
+    let m = Map::new((3, 3).into()).with_wrap(WrapMode::Torus);
+    assert_eq!(m.normalize_pos((0, 3).into()), Some((0, 0).into()));
+    assert_eq!(m.normalize_pos((1, -1).into()), Some((1, 2).into()));
+    assert_eq!(m.normalize_pos((3, 3).into()), Some((0, 0).into()), "(1, 1) is back at the origin after 3 steps");
+    assert_eq!(
+        m.itinerary_iter((1, 1).into()).collect::<Vec<_>>(),
+        vec![(0, 0).into(), (1, 1).into(), (2, 2).into()]
+    );
+
+    assert_eq!(torus_period((3, 3).into(), (3, 1).into()), 3);
+    assert_eq!(torus_period((4, 6).into(), (1, 2).into()), 12);
+    assert_eq!(torus_period((4, 6).into(), (2, 3).into()), 2);
+
+    let map = Map::parse(include_bytes!("sample.txt")).unwrap().with_wrap(WrapMode::Torus);
+    assert_eq!(map.itinerary_iter((3, 1).into()).count(), 11);
+    assert_eq!(BitMap::from(&map).count_trees((1, 2).into()), map.count_trees((1, 2).into()));
+}
+
+#[test]
+fn test_trees_hit() {
+    let map = Map::parse(include_bytes!("sample.txt")).unwrap();
+    assert_eq!(
+        map.trees_hit((3, 1).into()),
+        vec![(6, 2).into(), (1, 4).into(), (4, 5).into(), (10, 7).into(), (2, 8).into(), (5, 9).into(), (8, 10).into()]
+    );
+
+    // the puzzle's illustration, folded back onto a single copy of the map (the starting
+    // square isn't marked in the puzzle, but it's on our path)
+    let expected = "\
+O.##.......
+#..O#...#..
+.#....X..#.
+..#.#...#O#
+.X...##..#.
+..#.X#.....
+.#.#.#.O..#
+.#........X
+#.X#...#...
+#...#X....#
+.#..#...X.#";
+    assert_eq!(map.annotated((3, 1).into()), expected);
+}
@@ -1,10 +1,13 @@
 use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
 use std::ops::RangeInclusive;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
 struct Year(u64);
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
 enum Length {
     /// Centimeters (the correct unit)
     Cm(u64),
@@ -14,14 +17,14 @@ enum Length {
     Unspecified(u64),
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
 struct Color<'a>(&'a str);
 
 /// An identifier
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
 struct ID<'a>(&'a str);
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Serialize)]
 struct Passport<'a> {
     birth_year: Year,
     issue_year: Year,
@@ -33,6 +36,37 @@ struct Passport<'a> {
     country_id: Option<ID<'a>>,
 }
 
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Length::Cm(v) => write!(f, "{}cm", v),
+            Length::In(v) => write!(f, "{}in", v),
+            Length::Unspecified(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+// Display writes a passport back out in the input format, as a single line of key:value pairs
+impl fmt::Display for Passport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "byr:{} iyr:{} eyr:{} hgt:{} hcl:{} ecl:{} pid:{}",
+            self.birth_year.0,
+            self.issue_year.0,
+            self.expiration_year.0,
+            self.height,
+            self.hair_color.0,
+            self.eye_color.0,
+            self.passport_id.0
+        )?;
+        if let Some(ID(cid)) = self.country_id {
+            write!(f, " cid:{}", cid)?;
+        }
+        Ok(())
+    }
+}
+
 // make our grammar simpler with a type that has all fields optional
 #[derive(PartialEq, Debug, Default)]
 struct PassportBuilder<'a> {
@@ -44,6 +78,25 @@ struct PassportBuilder<'a> {
     eye_color: Option<Color<'a>>,
     passport_id: Option<ID<'a>>,
     country_id: Option<ID<'a>>,
+    /// Fields we don't know about, by key. They don't make a record invalid.
+    extras: HashMap<&'a str, &'a str>,
+}
+
+/// Everything that can be wrong with a single field of a record, see `PassportBuilder::validate()`
+#[derive(thiserror::Error, PartialEq, Debug)]
+enum FieldError {
+    #[error("missing field {0}")]
+    Missing(&'static str),
+
+    #[error("{field} is {value}, expected {}..={}", .allowed.start(), .allowed.end())]
+    OutOfRange {
+        field: &'static str,
+        value: u64,
+        allowed: RangeInclusive<u64>,
+    },
+
+    #[error("{field} is badly formatted: {value:?}")]
+    BadFormat { field: &'static str, value: String },
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -108,8 +161,96 @@ impl<'a> PassportBuilder<'a> {
         }
     }
 
+    // validate() applies the stricter rules of part 2. Unlike build(), it doesn't stop at the
+    // first problem: every missing or invalid field of the record is reported.
+    fn validate(&self) -> Result<Passport<'a>, Vec<FieldError>> {
+        let mut errors = Vec::new();
+
+        fn year(field: &'static str, year: Option<Year>, allowed: RangeInclusive<u64>, errors: &mut Vec<FieldError>) {
+            match year {
+                None => errors.push(FieldError::Missing(field)),
+                Some(Year(value)) if !allowed.contains(&value) => {
+                    errors.push(FieldError::OutOfRange { field, value, allowed })
+                }
+                Some(_) => {}
+            }
+        }
+        year("byr", self.birth_year, 1920..=2002, &mut errors);
+        year("iyr", self.issue_year, 2010..=2020, &mut errors);
+        year("eyr", self.expiration_year, 2020..=2030, &mut errors);
+
+        // build() (part 1) tolerates a height without a unit, but here it has to be cm or in
+        let (value, allowed) = match self.height {
+            None => (None, 0..=0),
+            Some(Length::Cm(v)) => (Some(v), 150..=193),
+            Some(Length::In(v)) => (Some(v), 59..=76),
+            Some(Length::Unspecified(v)) => {
+                errors.push(FieldError::BadFormat {
+                    field: "hgt",
+                    value: v.to_string(),
+                });
+                (Some(v), 0..=u64::MAX)
+            }
+        };
+        match value {
+            None => errors.push(FieldError::Missing("hgt")),
+            Some(value) if !allowed.contains(&value) => errors.push(FieldError::OutOfRange {
+                field: "hgt",
+                value,
+                allowed,
+            }),
+            Some(_) => {}
+        }
+
+        fn format(field: &'static str, value: Option<&str>, is_valid: fn(&str) -> bool, errors: &mut Vec<FieldError>) {
+            match value {
+                None => errors.push(FieldError::Missing(field)),
+                Some(value) if !is_valid(value) => errors.push(FieldError::BadFormat {
+                    field,
+                    value: value.to_string(),
+                }),
+                Some(_) => {}
+            }
+        }
+        format(
+            "hcl",
+            self.hair_color.map(|c| c.0),
+            |s| match s.strip_prefix('#') {
+                Some(hex) => hex.len() == 6 && hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')),
+                None => false,
+            },
+            &mut errors,
+        );
+        format(
+            "ecl",
+            self.eye_color.map(|c| c.0),
+            |s| ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"].contains(&s),
+            &mut errors,
+        );
+        format(
+            "pid",
+            self.passport_id.map(|id| id.0),
+            |s| s.len() == 9 && s.bytes().all(|b| b.is_ascii_digit()),
+            &mut errors,
+        );
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(Passport {
+            birth_year: self.birth_year.unwrap(),
+            issue_year: self.issue_year.unwrap(),
+            expiration_year: self.expiration_year.unwrap(),
+            height: self.height.unwrap(),
+            hair_color: self.hair_color.unwrap(),
+            eye_color: self.eye_color.unwrap(),
+            passport_id: self.passport_id.unwrap(),
+            country_id: self.country_id,
+        })
+    }
+
     // parse1() needs to accommodate fields in any order. Parse only one record.
-    fn parse1(input: &'a str) -> Self {
+    fn parse1(input: &'a str) -> Result<Self, Error> {
         let mut b: Self = Default::default();
 
         peg::parser! {
@@ -130,6 +271,16 @@ impl<'a> PassportBuilder<'a> {
                     / hcl(b) / ecl(b)
                     // IDs
                     / pid(b) / cid(b)
+                    // anything else
+                    / extra(b)
+
+                rule known_key()
+                    = ("byr" / "iyr" / "eyr" / "hgt" / "hcl" / "ecl" / "pid" / "cid") ":"
+
+                rule extra(b: &mut PassportBuilder<'input>)
+                    = !known_key() key:$(['a'..='z']+) ":" value:$((!separator()[_])+) {
+                        b.extras.insert(key, value);
+                    }
 
                 rule byr(b: &mut PassportBuilder<'input>)
                     = "byr:" year:year() { b.birth_year = Some(year) }
@@ -167,137 +318,120 @@ impl<'a> PassportBuilder<'a> {
                     / num:num() { Length::Unspecified(num) }
 
                 rule num() -> u64
-                    = s:$(['0'..='9']+) { s.parse().unwrap() }
+                    = s:$(['0'..='9']+) {? s.parse().or(Err("number")) }
 
                 rule id() -> ID<'input>
                     = s:$(['0'..='9' | 'a'..='z' | '#']+) { ID(s) }
             }
         }
 
-        parser::root(input, &mut b).unwrap_or_else(|e| panic!("Could not parse {}: {}", input, e));
-        b
+        parser::root(input, &mut b).map_err(|e| Error::ParseError(input.into(), e.to_string()))?;
+        Ok(b)
     }
+}
 
-    // parse2() needs to accommodate fields in any order. Parse only one record.
-    fn parse2(input: &'a str) -> Result<Self, Error> {
-        let mut b: Self = Default::default();
-
-        peg::parser! {
-            grammar parser() for str {
-
-                pub(crate) rule root(b: &mut PassportBuilder<'input>)
-                    = (field(b) separator()*)* ![_]
-
-                rule separator()
-                    = ['\n' | ' ']
-
-                rule field(b: &mut PassportBuilder<'input>)
-                    // years
-                    = byr(b) / iyr(b) / eyr(b)
-                    // height
-                    / hgt(b)
-                    // colors
-                    / hcl(b) / ecl(b)
-                    // IDs
-                    / pid(b) / cid(b)
-
-                rule byr(b: &mut PassportBuilder<'input>) -> ()
-                    = "byr:" year:year((1920..=2002)) { b.birth_year = Some(year); }
-
-                rule iyr(b: &mut PassportBuilder<'input>) -> ()
-                    = "iyr:" year:year((2010..=2020)) { b.issue_year = Some(year); }
-
-                rule eyr(b: &mut PassportBuilder<'input>) -> ()
-                    = "eyr:" year:year((2020..=2030)) { b.expiration_year = Some(year); }
-
-                rule year(range: RangeInclusive<u64>) -> Year
-                    = num:num() {?
-                        if range.contains(&num) {
-                            Ok(Year(num))
-                        } else {
-                            Err("year out of range")
-                        }
-                    }
-
-                rule hgt(b: &mut PassportBuilder<'input>)
-                    = "hgt:" height:length() {?
-                        match &height {
-                            Length::Cm(v) if !(150..=193).contains(v) => {
-                                Err("bad height (cm)")
-                            },
-                            Length::In(v) if !(59..=76).contains(v) => {
-                                Err("bad height (in)")
-                            },
-                            _ => {
-                                b.height = Some(height);
-                                Ok(())
-                            },
-                        }
-                    }
-
-                rule pid(b: &mut PassportBuilder<'input>)
-                    = "pid:" id:$(['0'..='9']*<9,9>) { b.passport_id = Some(ID(id)) }
 
-                rule cid(b: &mut PassportBuilder<'input>)
-                    = "cid:" id:$((!separator()[_])+) { b.country_id = Some(ID(id)) }
 
-                rule hcl(b: &mut PassportBuilder<'input>)
-                    = "hcl:" color:hcl0() { b.hair_color = Some(color) }
+/// How the records of an input fared: malformed records count as invalid passports, but we
+/// keep track of them separately from records that parsed but are missing fields.
+#[derive(PartialEq, Debug, Default)]
+struct Summary {
+    valid: usize,
+    parse_failures: usize,
+    validation_failures: usize,
+}
 
-                rule hcl0() -> Color<'input>
-                    = s:$("#" ['0'..='9' | 'a'..='f']*<6,6>) { Color(s) }
+impl Summary {
+    fn of<'a>(input: &'a str, is_valid: impl Fn(PassportBuilder<'a>) -> bool) -> Self {
+        let mut summary: Self = Default::default();
+        for record in input.split("\n\n") {
+            match PassportBuilder::parse1(record).map(&is_valid) {
+                Ok(true) => summary.valid += 1,
+                Ok(false) => summary.validation_failures += 1,
+                Err(_) => summary.parse_failures += 1,
+            }
+        }
+        summary
+    }
 
-                rule ecl(b: &mut PassportBuilder<'input>)
-                    = "ecl:" color:ecl0() { b.eye_color = Some(color) }
+    fn print(&self) {
+        println!("  {} passport records were valid", self.valid);
+        println!(
+            "  ({} records failed to parse, {} failed validation)",
+            self.parse_failures, self.validation_failures
+        );
+    }
+}
 
-                rule ecl0() -> Color<'input>
-                    = s:$("amb" / "blu" / "brn" / "gry" / "grn" / "hzl" / "oth") { Color(s) }
+/// What `--json` writes out: every passport that passes validate(), and how many didn't
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    passports: Vec<Passport<'a>>,
+    summary: JsonSummary,
+}
 
-                rule color() -> Color<'input>
-                    = s:$((!separator()[_])*) { Color(s) }
+#[derive(Serialize)]
+struct JsonSummary {
+    valid: usize,
+    invalid: usize,
+}
 
-                rule length() -> Length
-                    = num:num() "cm" { Length::Cm(num) }
-                    / num:num() "in" { Length::In(num) }
-                    / num:num() { Length::Unspecified(num) }
+impl<'a> JsonReport<'a> {
+    fn of(input: &'a str) -> Self {
+        let records: Vec<_> = input.split("\n\n").collect();
+        let passports: Vec<_> = records
+            .iter()
+            .filter_map(|record| PassportBuilder::parse1(record).ok())
+            .filter_map(|b| b.validate().ok())
+            .collect();
+        let summary = JsonSummary {
+            valid: passports.len(),
+            invalid: records.len() - passports.len(),
+        };
+        Self { passports, summary }
+    }
+}
 
-                rule num() -> u64
-                    = s:$(['0'..='9']+) { s.parse().unwrap() }
-            }
+/// unknown_fields() finds how many records contain fields we don't know about, and their keys
+fn unknown_fields(input: &str) -> (usize, BTreeSet<&str>) {
+    let mut records = 0;
+    let mut keys = BTreeSet::new();
+    for b in input.split("\n\n").filter_map(|record| PassportBuilder::parse1(record).ok()) {
+        if !b.extras.is_empty() {
+            records += 1;
+            keys.extend(b.extras.keys());
         }
-
-        parser::root(input, &mut b).map_err(|e| Error::ParseError(input.into(), e.to_string()))?;
-        Ok(b)
     }
+    (records, keys)
 }
 
-
-
 fn main() -> anyhow::Result<()> {
-    let results = include_str!("input.txt")
-        .split("\n\n")
-        .map(PassportBuilder::parse1)
-        .map(PassportBuilder::build);
+    let input = include_str!("input.txt");
 
-    let num_valid = results.filter(Result::is_ok).count();
-    println!("Part 1:");
-    println!("  {} passport records were valid", num_valid);
+    let (records, keys) = unknown_fields(input);
+    if records > 0 {
+        let keys: Vec<_> = keys.into_iter().collect();
+        eprintln!("warning: {} records contained unknown fields: {}", records, keys.join(", "));
+    }
 
-    let results2 = include_str!("input.txt")
-        .split("\n\n")
-        .map(|input| PassportBuilder::parse2(input).and_then(|b| b.build()));
+    if std::env::args().any(|arg| arg == "--json") {
+        serde_json::to_writer_pretty(std::io::stdout(), &JsonReport::of(input))?;
+        println!();
+        return Ok(());
+    }
 
-    let num_valid2 = results2.clone().filter(Result::is_ok).count();
+    println!("Part 1:");
+    Summary::of(input, |b| b.build().is_ok()).print();
 
     println!("Part 2:");
-    println!("  {} passport records were valid", num_valid2);
+    Summary::of(input, |b| b.validate().is_ok()).print();
 
     /*
     println!();
-    for n in results2 {
-        match n {
+    for b in input.split("\n\n").filter_map(|input| PassportBuilder::parse1(input).ok()) {
+        match b.validate() {
             Ok(passport) => println!("{:?}", passport),
-            Err(err) => println!("{:?}", err),
+            Err(errors) => println!("{:?}", errors),
         };
     }
     */
@@ -317,7 +451,203 @@ fn test_builder() {
         eye_color: Some(Color("#ee7812")),
         passport_id: Some(ID("00023437")),
         country_id: None,
+        extras: HashMap::new(),
     }
     .build()
     .is_ok());
 }
+
+#[test]
+fn test_parse1_malformed() {
+    // a stray ':' is a parse failure, not a panic
+    assert!(PassportBuilder::parse1("byr:1937 : iyr:2017").is_err());
+    // so is a number too big for a u64
+    assert!(PassportBuilder::parse1("byr:99999999999999999999").is_err());
+    assert!(PassportBuilder::parse1("hgt:99999999999999999999cm").is_err());
+    // an empty record parses, but is missing every field
+    assert_eq!(PassportBuilder::parse1("").unwrap(), Default::default());
+    assert!(PassportBuilder::parse1("").unwrap().build().is_err());
+
+    let input = "byr:1937 : iyr:2017\n\n\n\n".to_owned() + include_str!("sample1.txt");
+    assert_eq!(
+        Summary::of(&input, |b| b.build().is_ok()),
+        Summary {
+            valid: 2,
+            parse_failures: 1,
+            validation_failures: 3,
+        }
+    );
+}
+
+#[test]
+fn test_validate() {
+    let b = PassportBuilder::parse1("byr:1919 iyr:2010 hgt:170cm hcl:123abc ecl:brn pid:000000001").unwrap();
+    assert_eq!(
+        b.validate().unwrap_err(),
+        vec![
+            FieldError::OutOfRange {
+                field: "byr",
+                value: 1919,
+                allowed: 1920..=2002
+            },
+            FieldError::Missing("eyr"),
+            FieldError::BadFormat {
+                field: "hcl",
+                value: "123abc".into()
+            },
+        ]
+    );
+
+    let b = PassportBuilder::parse1("pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f").unwrap();
+    assert!(b.validate().is_ok());
+
+    let valid = include_str!("sample2.txt")
+        .split("\n\n")
+        .map(|record| PassportBuilder::parse1(record).unwrap().validate().is_ok())
+        .collect::<Vec<_>>();
+    assert_eq!(valid, vec![true, true, true, true, false, false, false, false]);
+}
+
+#[test]
+fn test_json_report() {
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Deserialize)]
+    struct OwnedPassport {
+        birth_year: u64,
+        height: HashMap<String, u64>,
+        hair_color: String,
+        passport_id: String,
+        country_id: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct OwnedSummary {
+        valid: usize,
+        invalid: usize,
+    }
+
+    #[derive(Deserialize)]
+    struct OwnedReport {
+        passports: Vec<OwnedPassport>,
+        summary: OwnedSummary,
+    }
+
+    let json = serde_json::to_string(&JsonReport::of(include_str!("sample2.txt"))).unwrap();
+    let report: OwnedReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(report.passports.len(), 4);
+    assert_eq!((report.summary.valid, report.summary.invalid), (4, 4));
+
+    let first = &report.passports[0];
+    assert_eq!(first.birth_year, 1980);
+    assert_eq!(first.height.get("In"), Some(&74));
+    assert_eq!(first.hair_color, "#623a2f");
+    assert_eq!(first.passport_id, "087499704");
+    assert_eq!(first.country_id, None);
+    assert_eq!(report.passports[1].country_id.as_deref(), Some("129"));
+}
+
+#[test]
+fn test_validate_height_units() {
+    let record = |hgt: &str| format!("byr:1980 iyr:2012 eyr:2030 hcl:#623a2f ecl:grn pid:087499704 hgt:{}", hgt);
+
+    let unitless = record("190");
+    let b = PassportBuilder::parse1(&unitless).unwrap();
+    assert_eq!(b.height, Some(Length::Unspecified(190)));
+    assert_eq!(
+        b.validate().unwrap_err(),
+        vec![FieldError::BadFormat {
+            field: "hgt",
+            value: "190".into()
+        }]
+    );
+    assert!(b.build().is_ok(), "part 1 doesn't care about units");
+
+    let inches = record("190in");
+    assert_eq!(
+        PassportBuilder::parse1(&inches).unwrap().validate().unwrap_err(),
+        vec![FieldError::OutOfRange {
+            field: "hgt",
+            value: 190,
+            allowed: 59..=76
+        }]
+    );
+
+    let centimeters = record("190cm");
+    assert!(PassportBuilder::parse1(&centimeters).unwrap().validate().is_ok());
+    let inches = record("60in");
+    assert!(PassportBuilder::parse1(&inches).unwrap().validate().is_ok());
+}
+
+#[test]
+fn test_unknown_fields() {
+    let input = "xyz:123 byr:1980 iyr:2012 eyr:2030 hgt:74in hcl:#623a2f ecl:grn pid:087499704 abc:#1\n\n\
+                 byr:1980 xyz:456";
+    let b = PassportBuilder::parse1(input.split("\n\n").next().unwrap()).unwrap();
+    assert_eq!(b.extras.get("xyz"), Some(&"123"));
+    assert_eq!(b.extras.get("abc"), Some(&"#1"));
+    assert!(b.validate().is_ok(), "unknown fields don't make a record invalid");
+
+    assert_eq!(unknown_fields(input), (2, ["abc", "xyz"].into_iter().collect()));
+
+    // known keys with bad values are still parse errors, not extras
+    assert!(PassportBuilder::parse1("byr:abc").is_err());
+}
+
+#[test]
+fn test_display_round_trip() {
+    let passports = [
+        Passport {
+            birth_year: Year(1937),
+            issue_year: Year(2017),
+            expiration_year: Year(2020),
+            height: Length::Cm(183),
+            hair_color: Color("#fffffd"),
+            eye_color: Color("gry"),
+            passport_id: ID("860033327"),
+            country_id: Some(ID("147")),
+        },
+        Passport {
+            birth_year: Year(2002),
+            issue_year: Year(2010),
+            expiration_year: Year(2030),
+            height: Length::In(60),
+            hair_color: Color("#123abc"),
+            eye_color: Color("brn"),
+            passport_id: ID("000000001"),
+            country_id: None,
+        },
+        Passport {
+            birth_year: Year(1926),
+            issue_year: Year(2018),
+            expiration_year: Year(1972),
+            height: Length::Unspecified(170),
+            hair_color: Color("#18171d"),
+            eye_color: Color("amb"),
+            passport_id: ID("186cm"),
+            country_id: Some(ID("100")),
+        },
+    ];
+    assert_eq!(
+        passports[0].to_string(),
+        "byr:1937 iyr:2017 eyr:2020 hgt:183cm hcl:#fffffd ecl:gry pid:860033327 cid:147"
+    );
+    for passport in &passports {
+        let s = passport.to_string();
+        assert_eq!(&PassportBuilder::parse1(&s).unwrap().build().unwrap(), passport);
+    }
+
+    for record in include_str!("sample1.txt").split("\n\n") {
+        if let Ok(passport) = PassportBuilder::parse1(record).unwrap().build() {
+            let s = passport.to_string();
+            assert_eq!(PassportBuilder::parse1(&s).unwrap().build().unwrap(), passport);
+        }
+    }
+    for record in include_str!("sample2.txt").split("\n\n") {
+        if let Ok(passport) = PassportBuilder::parse1(record).unwrap().validate() {
+            let s = passport.to_string();
+            assert_eq!(PassportBuilder::parse1(&s).unwrap().validate().unwrap(), passport);
+        }
+    }
+}
@@ -1,144 +1,302 @@
 use bitvec::prelude::*; // treat anything as a vector of... bits! exactly what we want to do here
+use anyhow::Context;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
 
-#[derive(Default, Debug, PartialEq)]
-struct Seat {
-    row: u8,
-    col: u8,
-}
-
-impl Seat {
-    const ROW_BITS: usize = 7;
-    const COL_BITS: usize = 3;
-
-    fn id(&self) -> u64 {
-        // bit shifting to multiply by the row number by 8
-        ((self.row as u64) << Self::COL_BITS) + (self.col as u64)
-    }
-
-    fn parse(input: &str) -> Self {
-        let bytes = input.as_bytes();
-        let mut res: Seat = Default::default();
-
-        {
-            // treat `res.row` as a collection of bits...
-            let row = BitSlice::<Msb0, _>::from_element_mut(&mut res.row);
-            // for each `F` or `B` element...
-            for (i, &b) in bytes[0..Self::ROW_BITS].iter().enumerate() {
-                // set the corresponding bit, in positions 1 through 7 (0-indexed)
-                row.set(
-                    (8 - Self::ROW_BITS) + i,
-                    match b {
-                        b'F' => false,
-                        b'B' => true,
-                        _ => panic!("unexpected row letter: {}", b as char),
-                    },
-                );
-            }
-        }
+#[derive(thiserror::Error, Debug, PartialEq)]
+enum SeatParseError {
+    #[error("expected a {expected}-letter boarding pass, but got {found} letters")]
+    Length { expected: usize, found: usize },
+    #[error("expected {expected} at index {index}, but got {ch:?}")]
+    UnexpectedChar {
+        index: usize,
+        ch: char,
+        expected: &'static str,
+    },
+}
 
-        {
-            let col = BitSlice::<Msb0, _>::from_element_mut(&mut res.col);
-            for (i, &b) in bytes[Self::ROW_BITS..][..Self::COL_BITS].iter().enumerate() {
-                col.set(
-                    (8 - Self::COL_BITS) + i,
-                    match b {
-                        b'L' => false,
-                        b'R' => true,
-                        _ => panic!("unexpected col letter: {}", b as char),
-                    },
-                );
-            }
-        }
+// check the whole boarding pass up front: exactly row_bits + col_bits letters,
+// F/B for the rows followed by L/R for the columns. Afterwards every byte is one of those four.
+fn validate_code(input: &str, row_bits: usize, col_bits: usize) -> Result<&[u8], SeatParseError> {
+    let expected = row_bits + col_bits;
+    let found = input.chars().count();
+    if found != expected {
+        return Err(SeatParseError::Length { expected, found });
+    }
 
-        res
+    for (index, ch) in input.chars().enumerate() {
+        let (expected, ok) = if index < row_bits {
+            ("F or B", matches!(ch, 'F' | 'B'))
+        } else {
+            ("L or R", matches!(ch, 'L' | 'R'))
+        };
+        if !ok {
+            return Err(SeatParseError::UnexpectedChar {
+                index,
+                ch,
+                expected,
+            });
+        }
     }
+
+    Ok(input.as_bytes())
 }
 
+// a seat on a plane with 2^R rows of 2^C columns. Stored as a u16 (R bits row then C bits column)
+// so the id is the value itself.
 // derive Ord to indicate that our type (more or less still a u16) has total ordering
 #[derive(Clone, Copy, Default, Debug, PartialEq, Eq, PartialOrd, Ord)]
-struct Seat2(u16);
+struct GenericSeat<const R: usize, const C: usize>(u16);
+
+// the puzzle's plane: 128 rows of 8 seats
+type Seat = GenericSeat<7, 3>;
 
-impl Seat2 {
-    // simplify Seat type to a u16 (its u10, 7bits row 3bits column) parse all ten bits in one go
-    // reverse the iterator and use Lsb0 (least-significant bit first) order, no need for arithmetic
-    fn parse(input: &str) -> Self {
-        let mut res: Seat2 = Default::default();
+impl<const R: usize, const C: usize> GenericSeat<R, C> {
+    const ROW_BITS: usize = R;
+    const COL_BITS: usize = C;
+    // every bit of the code has to fit in the u16
+    const BITS: usize = {
+        assert!(R + C <= 16, "a seat code can't be more than 16 letters");
+        R + C
+    };
+
+    fn new(id: u16) -> Self {
+        assert!(id >> Self::BITS == 0, "seat id {} doesn't fit in {} bits", id, Self::BITS);
+        GenericSeat(id)
+    }
+
+    fn id(&self) -> u16 {
+        self.0
+    }
+
+    fn row(&self) -> u16 {
+        self.0 >> Self::COL_BITS
+    }
+
+    fn col(&self) -> u16 {
+        self.0 & ((1 << Self::COL_BITS) - 1)
+    }
+
+    // parse all the bits in one go: reverse the iterator and use Lsb0
+    // (least-significant bit first) order, no need for arithmetic
+    fn parse(input: &str) -> Result<Self, SeatParseError> {
+        let bytes = validate_code(input, Self::ROW_BITS, Self::COL_BITS)?;
+        let mut res: Self = Default::default();
 
         let bits = BitSlice::<Lsb0, _>::from_element_mut(&mut res.0);
-        for (i, &b) in input.as_bytes().iter().rev().enumerate() {
-            bits.set(
-                i,
-                match b {
-                    b'F' | b'L' => false,
-                    b'B' | b'R' => true,
-                    _ => panic!("unexpected letter: {}", b as char),
-                },
-            )
+        for (i, &b) in bytes.iter().rev().enumerate() {
+            bits.set(i, matches!(b, b'B' | b'R'))
         }
 
-        res
+        Ok(res)
+    }
+
+    // the reverse of `parse`: walk the same Lsb0 bits from the most significant end,
+    // using F/B for the row bits and L/R for the column bits
+    fn to_code(self) -> String {
+        let bits = BitSlice::<Lsb0, _>::from_element(&self.0);
+        bits[..Self::BITS]
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, bit)| match (i < Self::ROW_BITS, *bit) {
+                (true, false) => 'F',
+                (true, true) => 'B',
+                (false, false) => 'L',
+                (false, true) => 'R',
+            })
+            .collect()
     }
 }
 
-fn main() {
-    let max_id = itertools::max(
-        include_str!("input.txt")
-            .lines()
-            .map(Seat::parse)
-            .map(|seat| seat.id()),
-    );
-    println!("Part 1:");
-    println!("  The maximum seat ID is {:?}", max_id);
+impl<const R: usize, const C: usize> fmt::Display for GenericSeat<R, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_code())
+    }
+}
 
-    let max_id = itertools::max(
-        include_str!("input.txt")
-            .lines()
-            .map(Seat2::parse)
-            .map(|seat| seat.0),
-    );
+impl<const R: usize, const C: usize> TryFrom<&str> for GenericSeat<R, C> {
+    type Error = SeatParseError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Self::parse(input)
+    }
+}
+
+// the row and column as separate fields, for when that's the more convenient shape
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+struct SeatPos {
+    row: u16,
+    col: u16,
+}
+
+impl<const R: usize, const C: usize> From<GenericSeat<R, C>> for SeatPos {
+    fn from(seat: GenericSeat<R, C>) -> Self {
+        SeatPos {
+            row: seat.row(),
+            col: seat.col(),
+        }
+    }
+}
+
+impl<const R: usize, const C: usize> From<SeatPos> for GenericSeat<R, C> {
+    fn from(pos: SeatPos) -> Self {
+        // bit shifting to multiply the row number by the number of columns
+        Self::new((pos.row << C) + pos.col)
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    // `day05 <path>` reads boarding passes from a file and `day05 -` from stdin,
+    // without arguments we use the embedded input.txt
+    let scan = match std::env::args().nth(1).as_deref() {
+        None => read_seat_ids(include_str!("input.txt").as_bytes())?,
+        Some("-") => read_seat_ids(std::io::stdin().lock())?,
+        Some(path) => {
+            let file = File::open(path).with_context(|| format!("couldn't open {}", path))?;
+            read_seat_ids(BufReader::new(file))?
+        }
+    };
+
+    for (lineno, e) in &scan.rejected {
+        eprintln!("line {}: {}", lineno, e);
+    }
+    println!("Rejected {} boarding passes", scan.rejected.len());
+
+    let max_id = scan.ids.iter().max();
+    println!("Part 1:");
     println!("  The maximum seat ID is {:?}", max_id);
 
     // part 2 wants missing seat
-    // collect all the IDs, sort them (from smallest to largest), then iterate, keeping track
-    // of the last one, and whenever the gap is more than 1 - that's it! We've found our seat.
-    // for our first iteration, we won't have a "last id", so we'll just use an Option
-    let mut ids: Vec<_> = include_str!("input.txt").lines().map(Seat2::parse).collect();
-    ids.sort();
+    let report = find_missing_seats(&scan.ids);
+    for gap in &report.gaps {
+        match gap.as_slice() {
+            [id] => println!("  Missing seat ID {}", id),
+            [first, .., last] => println!("  Missing seat IDs {}..={}", first, last),
+            [] => {}
+        }
+    }
+    if !report.duplicates.is_empty() {
+        println!("  Duplicate seat IDs {:?}", report.duplicates);
+    }
+
+    // with duplicates around, find_my_seat_xor() can't be trusted (see there)
+    match find_my_seat_xor(scan.ids.iter().copied()).filter(|_| report.duplicates.is_empty()) {
+        Some(id) => println!("Our seat ID is {}", id),
+        None => println!("Couldn't find a single free seat with both neighbours taken"),
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct SeatScan {
+    ids: Vec<u16>,
+    // boarding passes that didn't parse, as `(line_number, error)` pairs
+    rejected: Vec<(usize, SeatParseError)>,
+}
+
+// parse boarding passes as they are read, keeping only the seat ids rather than the lines
+fn read_seat_ids(reader: impl BufRead) -> std::io::Result<SeatScan> {
+    let mut scan = SeatScan::default();
+    for (index, line) in reader.lines().enumerate() {
+        match Seat::parse(&line?) {
+            Ok(seat) => scan.ids.push(seat.id()),
+            Err(e) => scan.rejected.push((index + 1, e)),
+        }
+    }
+    Ok(scan)
+}
 
-    let mut last_id: Option<Seat2> = None;
+// XOR of every number in 0..=n, which repeats with period 4
+fn xor_upto(n: u16) -> u16 {
+    match n % 4 {
+        0 => n,
+        1 => 1,
+        2 => n + 1,
+        _ => 0,
+    }
+}
+
+// no need to sort: XOR-ing all the ids we saw against every id in min..=max cancels out
+// everything that's present, leaving the one that's missing. Counting the ids on the way
+// tells us whether exactly one is missing, otherwise the XOR would be meaningless.
+// Without remembering every id there's no telling a duplicate apart for sure: one that makes up
+// for an extra missing id in the count is usually caught because the sum disagrees with the XOR,
+// or the answer isn't strictly between min and max, but some (like 0, 0, 3, 4, ...) still give a
+// wrong answer. find_missing_seats() is the one that reports duplicates.
+fn find_my_seat_xor(ids: impl Iterator<Item = u16>) -> Option<u16> {
+    let (mut min, mut max, mut xor, mut sum, mut count) = (u16::MAX, 0, 0, 0u64, 0usize);
     for id in ids {
-        if let Some(last_id) = last_id {
-            let gap = id.0 - last_id.0;
-            if gap > 1 {
-                println!("Our seat ID is {}", last_id.0 + 1);
-                return;
+        min = min.min(id);
+        max = max.max(id);
+        xor ^= id;
+        sum += u64::from(id);
+        count += 1;
+    }
+
+    if count == 0 || usize::from(max - min) != count {
+        return None;
+    }
+    let missing = xor ^ xor_upto(max) ^ min.checked_sub(1).map_or(0, xor_upto);
+    let expected_sum = (u64::from(min) + u64::from(max)) * (u64::from(max - min) + 1) / 2;
+    (min < missing && missing < max && expected_sum.checked_sub(sum) == Some(u64::from(missing))).then_some(missing)
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct MissingReport {
+    // every run of absent ids between the lowest and highest id present
+    gaps: Vec<Vec<u16>>,
+    // ids that appear on more than one boarding pass
+    duplicates: Vec<u16>,
+    // the only gap of exactly one seat, if there is just one such gap
+    my_seat: Option<u16>,
+}
+
+// collect all the IDs, sort them (from smallest to largest), then walk them in pairs:
+// equal neighbours are duplicates, and a difference of more than 1 is a gap. Our seat is
+// the one missing ID whose neighbours (id - 1 and id + 1) are both present.
+fn find_missing_seats(ids: &[u16]) -> MissingReport {
+    let mut ids = ids.to_vec();
+    ids.sort_unstable();
+
+    let mut report = MissingReport::default();
+    for (&prev, &next) in ids.iter().zip(ids.iter().skip(1)) {
+        if prev == next {
+            if report.duplicates.last() != Some(&next) {
+                report.duplicates.push(next);
             }
+        } else if next - prev > 1 {
+            report.gaps.push((prev + 1..next).collect());
         }
-        last_id = Some(id);
     }
-}
 
+    let mut singles = report.gaps.iter().filter(|gap| gap.len() == 1);
+    if let (Some(gap), None) = (singles.next(), singles.next()) {
+        report.my_seat = Some(gap[0]);
+    }
+
+    report
+}
 
 #[test]
 fn test_parse() {
     let input = "FBFBBFFRLR";
-    let seat = Seat::parse(input);
-    assert_eq!(seat, Seat { row: 44, col: 5 });
+    let seat = Seat::parse(input).unwrap();
+    assert_eq!(SeatPos::from(seat), SeatPos { row: 44, col: 5 });
+    assert_eq!(Seat::from(SeatPos { row: 44, col: 5 }), seat);
 }
 
 #[test]
 fn test_seat_id() {
     macro_rules! validate {
         ($input: expr, $row: expr, $col: expr, $id: expr) => {
-            let seat = Seat::parse($input);
-            assert_eq!(
-                seat,
-                Seat {
-                    row: $row,
-                    col: $col
-                }
-            );
+            let seat = Seat::parse($input).unwrap();
+            assert_eq!(seat.row(), $row);
+            assert_eq!(seat.col(), $col);
             assert_eq!(seat.id(), $id);
+            assert_eq!(Seat::from(SeatPos::from(seat)), seat);
         };
     }
 
@@ -148,8 +306,164 @@ fn test_seat_id() {
 }
 
 #[test]
-fn test_seat2_id() {
-    assert_eq!(Seat2::parse("BFFFBBFRRR"), Seat2(567));
-    assert_eq!(Seat2::parse("FFFBBBFRRR"), Seat2(119));
-    assert_eq!(Seat2::parse("BBFFBBFRLL"), Seat2(820));
+fn test_parse_errors() {
+    assert_eq!(Seat::try_from("FBFBBFFRLR"), Ok(Seat::new(357)));
+    assert_eq!(
+        Seat::try_from("FBFBBFFRL"),
+        Err(SeatParseError::Length {
+            expected: 10,
+            found: 9
+        })
+    );
+    assert_eq!(
+        Seat::try_from("FBFBBFFRLX"),
+        Err(SeatParseError::UnexpectedChar {
+            index: 9,
+            ch: 'X',
+            expected: "L or R"
+        })
+    );
+    // rows and columns can't be swapped around
+    assert_eq!(
+        Seat::try_from("FBFBBFRRLR"),
+        Err(SeatParseError::UnexpectedChar {
+            index: 6,
+            ch: 'R',
+            expected: "F or B"
+        })
+    );
+    assert!(Seat::try_from("FBFBBFFRLRR").is_err());
+}
+
+#[test]
+fn test_find_missing_seats() {
+    // a whole missing row at 20..=27 and our seat at 33
+    let ids: Vec<u16> = (10..20).chain(28..33).chain(34..40).rev().collect();
+    assert_eq!(
+        find_missing_seats(&ids),
+        MissingReport {
+            gaps: vec![(20..28).collect(), vec![33]],
+            duplicates: vec![],
+            my_seat: Some(33),
+        }
+    );
+
+    // two gaps of one seat each means we can't tell which one is ours
+    let ids = [1, 2, 4, 5, 7, 8];
+    let report = find_missing_seats(&ids);
+    assert_eq!(report.gaps, vec![vec![3], vec![6]]);
+    assert_eq!(report.my_seat, None);
+}
+
+#[test]
+fn test_find_missing_seats_duplicates() {
+    let ids = [5, 6, 8, 7, 6, 10, 6, 11];
+    assert_eq!(
+        find_missing_seats(&ids),
+        MissingReport {
+            gaps: vec![vec![9]],
+            duplicates: vec![6],
+            my_seat: Some(9),
+        }
+    );
+}
+
+#[test]
+fn test_to_code() {
+    assert_eq!(Seat::new(357).to_code(), "FBFBBFFRLR");
+    assert_eq!(Seat::new(567).to_string(), "BFFFBBFRRR");
+
+    for id in 0..1024 {
+        let code = Seat::new(id).to_code();
+        assert_eq!(Seat::parse(&code).unwrap().id(), id);
+    }
+}
+
+#[test]
+fn test_generic_widths() {
+    // a toy plane with 16 rows of 4 seats
+    type ToySeat = GenericSeat<4, 2>;
+
+    let seat = ToySeat::parse("BFBFLR").unwrap();
+    assert_eq!(SeatPos::from(seat), SeatPos { row: 10, col: 1 });
+    assert_eq!(seat.id(), (10 << 2) + 1);
+    assert_eq!(seat.to_code(), "BFBFLR");
+    assert_eq!(ToySeat::from(SeatPos { row: 10, col: 1 }), seat);
+
+    assert_eq!(
+        ToySeat::parse("FBFBBFFRLR"),
+        Err(SeatParseError::Length {
+            expected: 6,
+            found: 10
+        })
+    );
+    assert_eq!(
+        ToySeat::parse("BFBFRB"),
+        Err(SeatParseError::UnexpectedChar {
+            index: 5,
+            ch: 'B',
+            expected: "L or R"
+        })
+    );
+
+    for id in 0..64 {
+        assert_eq!(ToySeat::parse(&ToySeat::new(id).to_code()).unwrap().id(), id);
+    }
+}
+
+#[test]
+fn test_find_my_seat_xor() {
+    // shaped like the real input: a contiguous block of ids, out of order, with one hole
+    for (lo, hole, hi) in [(0, 1, 10), (48, 603, 987), (13, 14, 1023), (100, 101, 103)] {
+        let mut ids: Vec<u16> = (lo..=hi).rev().filter(|&id| id != hole).collect();
+        let third = ids.len() / 3;
+        ids.rotate_left(third);
+
+        assert_eq!(find_my_seat_xor(ids.iter().copied()), Some(hole));
+        assert_eq!(find_missing_seats(&ids).my_seat, Some(hole));
+    }
+
+    assert_eq!(find_my_seat_xor([3, 4, 5].iter().copied()), None);
+    assert_eq!(find_my_seat_xor([3, 5, 7].iter().copied()), None);
+    assert_eq!(find_my_seat_xor(std::iter::empty()), None);
+
+    // a duplicate that makes up for a second missing seat in the count is caught by the range or the sum...
+    assert_eq!(find_my_seat_xor([3, 4, 4, 7].iter().copied()), None);
+    assert_eq!(find_my_seat_xor([3, 4, 4, 6].iter().copied()), None);
+    assert_eq!(find_my_seat_xor([0, 3, 4, 4, 5, 6, 7, 8, 9].iter().copied()), None);
+    // ...but not always, which is why main checks find_missing_seats() for duplicates first
+    let ids = [0, 0, 3, 4, 5, 6, 7, 8, 9];
+    assert_eq!(find_my_seat_xor(ids.iter().copied()), Some(3));
+    assert_eq!(find_missing_seats(&ids).duplicates, vec![0]);
+
+    // more ids than fit in a u16 count, all distinct but for the one hole
+    let ids = (0..=u16::MAX).filter(|&id| id != 40_000);
+    assert_eq!(find_my_seat_xor(ids), Some(40_000));
+}
+
+#[test]
+fn test_read_seat_ids() {
+    let input = "FBFBBFFRLR\nBFFFBBFRRR\nFBFBBFFRL\nFFFBBBFRRR\nBBFFBBFRLX\nBBFFBBFRLL\n";
+    let scan = read_seat_ids(std::io::Cursor::new(input)).unwrap();
+    assert_eq!(scan.ids, vec![357, 567, 119, 820]);
+    assert_eq!(
+        scan.rejected,
+        vec![
+            (
+                3,
+                SeatParseError::Length {
+                    expected: 10,
+                    found: 9
+                }
+            ),
+            (
+                5,
+                SeatParseError::UnexpectedChar {
+                    index: 9,
+                    ch: 'X',
+                    expected: "L or R"
+                }
+            ),
+        ]
+    );
 }
@@ -0,0 +1,675 @@
+// in `day06/src/lib.rs`
+
+use std::io::{self, BufRead};
+
+// each person answers "yes" to some of 26 questions, a to z, which fits in the low bits of an integer:
+// a union of answers is a bitwise OR, an intersection is a bitwise AND, and the number of
+// questions is the number of bits set. No allocation needed, per person or per group.
+// Variants of the puzzle can use up to 64 questions, see `Alphabet`.
+
+/// The most questions an [`Alphabet`] can have, one per bit of a `u64` mask
+pub const MAX_QUESTIONS: usize = 64;
+
+// marks a byte that isn't part of the alphabet
+const NOT_A_QUESTION: u8 = u8::MAX;
+
+/// The letters that stand for questions, each mapped to a bit of the answer masks in the order
+/// they were given.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alphabet {
+    letters: [u8; MAX_QUESTIONS],
+    len: usize,
+    bits: [u8; 256],
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum AlphabetError {
+    #[error("an alphabet can have at most {} letters", MAX_QUESTIONS)]
+    TooLong,
+    #[error("{0:?} appears in the alphabet more than once")]
+    Duplicate(char),
+    #[error("alphabet letters must be ASCII, but got {0:?}")]
+    NotAscii(char),
+    #[error("range {0}-{1} is backwards")]
+    BackwardsRange(char, char),
+}
+
+impl Alphabet {
+    /// The puzzle's questions, 'a' to 'z'
+    pub const LOWERCASE: Alphabet = Alphabet::range(b'a', b'z');
+
+    const fn empty() -> Self {
+        Alphabet {
+            letters: [0; MAX_QUESTIONS],
+            len: 0,
+            bits: [NOT_A_QUESTION; 256],
+        }
+    }
+
+    const fn range(first: u8, last: u8) -> Self {
+        let mut alphabet = Alphabet::empty();
+        let mut letter = first;
+        while letter <= last {
+            alphabet.letters[alphabet.len] = letter;
+            alphabet.bits[letter as usize] = alphabet.len as u8;
+            alphabet.len += 1;
+            letter += 1;
+        }
+        alphabet
+    }
+
+    /// Parses a list of letters and ranges of letters, like `a-zA-Z0-9`
+    pub fn parse(spec: &str) -> Result<Self, AlphabetError> {
+        let mut alphabet = Alphabet::empty();
+        let chars: Vec<char> = spec.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let (first, last) = match chars[i..] {
+                [first, '-', last, ..] => {
+                    i += 3;
+                    (first, last)
+                }
+                [letter, ..] => {
+                    i += 1;
+                    (letter, letter)
+                }
+                [] => unreachable!(),
+            };
+            for ch in [first, last] {
+                if !ch.is_ascii() {
+                    return Err(AlphabetError::NotAscii(ch));
+                }
+            }
+            if first > last {
+                return Err(AlphabetError::BackwardsRange(first, last));
+            }
+            for letter in first as u8..=last as u8 {
+                alphabet.push(letter)?;
+            }
+        }
+        Ok(alphabet)
+    }
+
+    fn push(&mut self, letter: u8) -> Result<(), AlphabetError> {
+        if self.bits[letter as usize] != NOT_A_QUESTION {
+            return Err(AlphabetError::Duplicate(letter as char));
+        }
+        if self.len == MAX_QUESTIONS {
+            return Err(AlphabetError::TooLong);
+        }
+        self.letters[self.len] = letter;
+        self.bits[letter as usize] = self.len as u8;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// How many questions there are
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The bit that stands for `ch`, if it's one of the questions
+    pub fn bit(&self, ch: char) -> Option<u32> {
+        let bit = *self.bits.get(ch as usize)?;
+        (bit != NOT_A_QUESTION).then_some(bit as u32)
+    }
+
+    /// The letter for the question at `bit`
+    pub fn letter(&self, bit: usize) -> char {
+        self.letters[..self.len][bit] as char
+    }
+
+    /// The questions a person answered "yes" to, as a bit per letter.
+    /// Anything that isn't in the alphabet is ignored.
+    pub fn mask(&self, line: &str) -> u64 {
+        line.chars()
+            .filter_map(|ch| self.bit(ch))
+            .fold(0, |mask, bit| mask | (1 << bit))
+    }
+
+    /// [`parse_groups`] with this alphabet's questions
+    pub fn parse_groups(&self, input: &str) -> Result<Vec<Group>, InputError> {
+        parse_groups_with(input, self, Err)
+    }
+
+    /// [`parse_groups_lenient`] with this alphabet's questions
+    pub fn parse_groups_lenient(&self, input: &str) -> (Vec<Group>, Vec<InputError>) {
+        let mut warnings = Vec::new();
+        let groups = parse_groups_with(input, self, |e| {
+            warnings.push(e);
+            Ok(())
+        })
+        .expect("lenient parsing never fails");
+        (groups, warnings)
+    }
+
+    /// [`group_stats`] with this alphabet's questions
+    pub fn group_stats(&self, input: &str) -> Vec<GroupStats> {
+        let mut stats = Vec::new();
+        for_each_group(input.lines().map(Ok::<_, ()>), self, |_| Ok(()), StatsBuilder::add, |group| {
+            stats.push(group.finish())
+        })
+        .expect("lenient parsing never fails");
+        stats
+    }
+
+    /// [`part1`] with this alphabet's questions
+    pub fn part1(&self, input: &str) -> usize {
+        self.group_stats(input).iter().map(|group| group.any_yes).sum()
+    }
+
+    /// [`part2`] with this alphabet's questions
+    pub fn part2(&self, input: &str) -> usize {
+        self.group_stats(input).iter().map(|group| group.all_yes).sum()
+    }
+
+    /// [`process_groups`] with this alphabet's questions
+    pub fn process_groups<R: BufRead>(&self, reader: R) -> io::Result<(usize, usize)> {
+        process_groups_with(reader, self)
+    }
+
+    /// Reads groups a line at a time like [`process_groups`], handing the statistics for each one to `on_group` as
+    /// soon as it ends. `on_error` decides whether a problem aborts reading (by returning it) or is skipped over, as
+    /// with [`parse_groups`] and [`parse_groups_lenient`].
+    pub fn read_group_stats<R: BufRead>(
+        &self,
+        reader: R,
+        mut on_error: impl FnMut(InputError) -> Result<(), InputError>,
+        mut on_group: impl FnMut(GroupStats),
+    ) -> Result<(), ReadError> {
+        let lines = reader.lines().map(|line| line.map_err(ReadError::from));
+        for_each_group(
+            lines,
+            self,
+            |e| on_error(e).map_err(ReadError::from),
+            StatsBuilder::add,
+            |group| on_group(group.finish()),
+        )
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::LOWERCASE
+    }
+}
+
+/// The questions a person answered "yes" to, as a bit per letter ('a' is bit 0).
+/// Anything that isn't a lowercase letter is ignored.
+pub fn answers_mask(line: &str) -> u64 {
+    Alphabet::LOWERCASE.mask(line)
+}
+
+/// Problems found by [`parse_groups`]. Groups, lines and columns are all numbered from 1.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum InputError {
+    #[error("group {group}, line {line}: {ch:?} at column {col} isn't one of the questions")]
+    UnexpectedChar {
+        group: usize,
+        line: usize,
+        col: usize,
+        ch: char,
+    },
+    #[error("group {group}, line {line}: empty line inside a group")]
+    EmptyLine { group: usize, line: usize },
+}
+
+/// Problems found by [`Alphabet::read_group_stats`]: either reading failed, or the input is wrong
+#[derive(thiserror::Error, Debug)]
+pub enum ReadError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Input(#[from] InputError),
+}
+
+/// The answers of everyone in a group, one mask per person
+#[derive(Debug, Default, PartialEq)]
+pub struct Group {
+    pub people: Vec<u64>,
+}
+
+impl Group {
+    pub fn stats(&self) -> GroupStats {
+        GroupStats::from_masks(self.people.iter().copied())
+    }
+
+    /// The number of questions anyone in the group answered "yes" to
+    pub fn any_yes(&self) -> usize {
+        self.stats().any_yes
+    }
+
+    /// The number of questions everyone in the group answered "yes" to
+    pub fn all_yes(&self) -> usize {
+        self.stats().all_yes
+    }
+}
+
+/// Everything we know about a group's answers; both parts of the puzzle are sums over these
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupStats {
+    /// How many people are in the group
+    pub people: usize,
+    /// How many questions anyone in the group answered "yes" to
+    pub any_yes: usize,
+    /// How many questions everyone in the group answered "yes" to
+    pub all_yes: usize,
+    /// How many people answered "yes" to each question, by bit; only the alphabet's first
+    /// [`Alphabet::len`] entries are used. Counted in a `usize` like `people`, since a group can have
+    /// more than 255 people who all said "yes".
+    pub per_question: [usize; MAX_QUESTIONS],
+}
+
+impl Default for GroupStats {
+    fn default() -> Self {
+        GroupStats {
+            people: 0,
+            any_yes: 0,
+            all_yes: 0,
+            per_question: [0; MAX_QUESTIONS],
+        }
+    }
+}
+
+impl GroupStats {
+    pub fn from_masks(masks: impl IntoIterator<Item = u64>) -> Self {
+        let mut builder = StatsBuilder::default();
+        for mask in masks {
+            builder.add(mask);
+        }
+        builder.finish()
+    }
+}
+
+// GroupStats for a group that's still being read: the union and intersection of everyone's answers so far, and the
+// counts, so a group takes the same memory however many people are in it
+#[derive(Default)]
+struct StatsBuilder {
+    stats: GroupStats,
+    any: u64,
+    all: u64,
+}
+
+impl StatsBuilder {
+    fn add(&mut self, mask: u64) {
+        // the intersection of no people at all is empty, so only start from all ones once we have someone
+        self.all = if self.stats.people == 0 { mask } else { self.all & mask };
+        self.any |= mask;
+        self.stats.people += 1;
+        for (question, count) in self.stats.per_question.iter_mut().enumerate() {
+            *count += (mask >> question & 1) as usize;
+        }
+    }
+
+    fn finish(mut self) -> GroupStats {
+        self.stats.any_yes = self.any.count_ones() as usize;
+        self.stats.all_yes = self.all.count_ones() as usize;
+        self.stats
+    }
+}
+
+/// Parses the input into groups, failing on the first character that isn't a lowercase letter,
+/// or on a blank line that doesn't separate two groups (a person without any answers).
+pub fn parse_groups(input: &str) -> Result<Vec<Group>, InputError> {
+    Alphabet::LOWERCASE.parse_groups(input)
+}
+
+/// Like [`parse_groups`], but skips over bad characters and empty lines, returning them as
+/// warnings alongside the groups.
+pub fn parse_groups_lenient(input: &str) -> (Vec<Group>, Vec<InputError>) {
+    Alphabet::LOWERCASE.parse_groups_lenient(input)
+}
+
+// The one rule for splitting input into groups, whatever it's read from: one person per line, and a blank line ends
+// the current group. A blank line that doesn't end a group (at the start, or right after another blank line) would
+// be a person without any answers, so it goes to `on_error` like a character outside the alphabet does. When
+// `on_error` returns Ok, the line or character is skipped over.
+// Each person's answers are handed to `add_person` along with the group they're in, which goes to `on_group` once
+// it's complete: a `Group` keeps every mask, a `StatsBuilder` only running totals.
+fn for_each_group<S: AsRef<str>, E, G: Default>(
+    lines: impl Iterator<Item = Result<S, E>>,
+    alphabet: &Alphabet,
+    mut on_error: impl FnMut(InputError) -> Result<(), E>,
+    mut add_person: impl FnMut(&mut G, u64),
+    mut on_group: impl FnMut(G),
+) -> Result<(), E> {
+    let mut current = G::default();
+    let mut people = 0;
+    let mut groups = 0;
+    // a blank line right after a group ends it, any further blank line is a person with no answers
+    let mut after_blank = false;
+
+    for (index, text) in lines.enumerate() {
+        let text = text?;
+        let text = text.as_ref();
+        let line = index + 1;
+        let group = groups + 1;
+
+        if text.is_empty() {
+            if after_blank || index == 0 {
+                on_error(InputError::EmptyLine { group, line })?;
+            } else if people > 0 {
+                on_group(std::mem::take(&mut current));
+                people = 0;
+                groups += 1;
+            }
+            after_blank = true;
+            continue;
+        }
+        after_blank = false;
+
+        let mut mask = 0;
+        for (col, ch) in text.chars().enumerate() {
+            if let Some(bit) = alphabet.bit(ch) {
+                mask |= 1 << bit;
+            } else {
+                on_error(InputError::UnexpectedChar {
+                    group,
+                    line,
+                    col: col + 1,
+                    ch,
+                })?;
+            }
+        }
+        add_person(&mut current, mask);
+        people += 1;
+    }
+
+    // the last group doesn't need a blank line after it
+    if people > 0 {
+        on_group(current);
+    }
+    Ok(())
+}
+
+// `on_error` decides whether a problem aborts parsing or is skipped over
+fn parse_groups_with(
+    input: &str,
+    alphabet: &Alphabet,
+    on_error: impl FnMut(InputError) -> Result<(), InputError>,
+) -> Result<Vec<Group>, InputError> {
+    let mut groups = Vec::new();
+    for_each_group(
+        input.lines().map(Ok),
+        alphabet,
+        on_error,
+        |group: &mut Group, mask| group.people.push(mask),
+        |group| groups.push(group),
+    )?;
+    Ok(groups)
+}
+
+/// The statistics for every group in the input, in order.
+/// Anything [`parse_groups_lenient`] would warn about is skipped over.
+pub fn group_stats(input: &str) -> Vec<GroupStats> {
+    Alphabet::LOWERCASE.group_stats(input)
+}
+
+/// Part 1: the sum over all groups of the questions anyone in the group answered "yes" to
+pub fn part1(input: &str) -> usize {
+    Alphabet::LOWERCASE.part1(input)
+}
+
+/// Part 2: the sum over all groups of the questions everyone in the group answered "yes" to
+pub fn part2(input: &str) -> usize {
+    Alphabet::LOWERCASE.part2(input)
+}
+
+/// Both parts at once, reading the input a line at a time and keeping only running totals for the
+/// current group, so memory use doesn't grow with the input. Returns `(part1, part2)`.
+/// Anything [`parse_groups_lenient`] would warn about is skipped over.
+pub fn process_groups<R: BufRead>(reader: R) -> io::Result<(usize, usize)> {
+    process_groups_with(reader, &Alphabet::LOWERCASE)
+}
+
+fn process_groups_with<R: BufRead>(reader: R, alphabet: &Alphabet) -> io::Result<(usize, usize)> {
+    let (mut part1, mut part2) = (0, 0);
+    for_each_group(reader.lines(), alphabet, |_| Ok(()), StatsBuilder::add, |group| {
+        let stats = group.finish();
+        part1 += stats.any_yes;
+        part2 += stats.all_yes;
+    })?;
+    Ok((part1, part2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // im provides a set of immutable data structures, including HashSet with a unions method
+    use im::HashSet;
+
+    const SAMPLE: &str = include_str!("sample.txt");
+
+    #[test]
+    fn test_answers_mask() {
+        assert_eq!(answers_mask(""), 0);
+        assert_eq!(answers_mask("a"), 0b1);
+        assert_eq!(answers_mask("abc"), 0b111);
+        assert_eq!(answers_mask("zz"), 1 << 25);
+    }
+
+    #[test]
+    fn test_parse_groups() {
+        let groups = parse_groups(SAMPLE).unwrap();
+        assert_eq!(groups.len(), 5);
+        assert_eq!(
+            groups[1],
+            Group {
+                people: vec![0b1, 0b10, 0b100]
+            }
+        );
+        assert_eq!(groups.iter().map(Group::any_yes).sum::<usize>(), 11);
+        assert_eq!(groups.iter().map(Group::all_yes).sum::<usize>(), 6);
+
+        // a trailing newline doesn't make an extra group
+        assert_eq!(parse_groups("ab\nb\n").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_groups_digit() {
+        let input = "abc\n\na\nb7\nc\n";
+        assert_eq!(
+            parse_groups(input),
+            Err(InputError::UnexpectedChar {
+                group: 2,
+                line: 4,
+                col: 2,
+                ch: '7'
+            })
+        );
+
+        let (groups, warnings) = parse_groups_lenient(input);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            groups[1],
+            Group {
+                people: vec![0b1, 0b10, 0b100]
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_groups_empty_line() {
+        // the second blank line is a person who answered nothing, not another separator
+        let input = "abc\n\na\n\n\nb\n";
+        assert_eq!(parse_groups(input), Err(InputError::EmptyLine { group: 3, line: 5 }));
+
+        let (groups, warnings) = parse_groups_lenient(input);
+        assert_eq!(warnings, vec![InputError::EmptyLine { group: 3, line: 5 }]);
+        assert_eq!(groups.len(), 3);
+    }
+
+    #[test]
+    fn test_same_groups_everywhere() {
+        // every entry point splits groups the same way: an extra blank line fails strict parsing, and is skipped
+        // over by everything else
+        for input in ["a\n\n\nb\n", "\na\n\nb", "a\n\nb\n\n\n"] {
+            assert!(matches!(parse_groups(input), Err(InputError::EmptyLine { .. })), "{:?}", input);
+
+            let (groups, warnings) = parse_groups_lenient(input);
+            assert_eq!(groups, vec![Group { people: vec![0b1] }, Group { people: vec![0b10] }], "{:?}", input);
+            assert_eq!(warnings.len(), 1, "{:?}", input);
+
+            assert_eq!(group_stats(input), groups.iter().map(Group::stats).collect::<Vec<_>>(), "{:?}", input);
+            assert_eq!((part1(input), part2(input)), (2, 2), "{:?}", input);
+            assert_eq!(process_groups(input.as_bytes()).unwrap(), (2, 2), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_group_stats() {
+        let per_question = |counts: &[(char, usize)]| {
+            let mut per_question = [0; MAX_QUESTIONS];
+            for &(question, count) in counts {
+                per_question[(question as u8 - b'a') as usize] = count;
+            }
+            per_question
+        };
+        let stats = |people, any_yes, all_yes, counts: &[(char, usize)]| GroupStats {
+            people,
+            any_yes,
+            all_yes,
+            per_question: per_question(counts),
+        };
+
+        assert_eq!(
+            group_stats(SAMPLE),
+            vec![
+                stats(1, 3, 3, &[('a', 1), ('b', 1), ('c', 1)]),
+                stats(3, 3, 0, &[('a', 1), ('b', 1), ('c', 1)]),
+                stats(2, 3, 1, &[('a', 2), ('b', 1), ('c', 1)]),
+                stats(4, 1, 1, &[('a', 4)]),
+                stats(1, 1, 1, &[('b', 1)]),
+            ]
+        );
+        assert_eq!(parse_groups(SAMPLE).unwrap()[2].stats(), group_stats(SAMPLE)[2]);
+
+        // far more people than fit in a u8, all answering the same question
+        let crowd = "a\n".repeat(300);
+        assert_eq!(group_stats(&crowd), vec![stats(300, 1, 1, &[('a', 300)])]);
+    }
+
+    #[test]
+    fn test_process_groups() {
+        assert_eq!(process_groups(io::Cursor::new(SAMPLE)).unwrap(), (11, 6));
+        // no trailing newline, and extra blank lines between groups
+        assert_eq!(process_groups(io::Cursor::new("ab\nac\n\n\n\nxyz")).unwrap(), (6, 4));
+        assert_eq!(process_groups(io::Cursor::new("")).unwrap(), (0, 0));
+
+        let input = include_str!("input.txt");
+        assert_eq!(process_groups(input.as_bytes()).unwrap(), (part1(input), part2(input)));
+    }
+
+    #[test]
+    fn test_read_group_stats() {
+        let input = "abc\n\na\nb7\nc\n";
+        let read = |on_error: fn(InputError) -> Result<(), InputError>| {
+            let mut stats = Vec::new();
+            Alphabet::LOWERCASE
+                .read_group_stats(input.as_bytes(), on_error, |group| stats.push(group))
+                .map(|_| stats)
+        };
+
+        // the same policies as parse_groups and parse_groups_lenient
+        assert!(matches!(
+            read(Err),
+            Err(ReadError::Input(InputError::UnexpectedChar { ch: '7', .. }))
+        ));
+        assert_eq!(read(|_| Ok(())).unwrap(), group_stats(input));
+        assert_eq!(
+            Alphabet::parse("a-z0-9").unwrap().read_group_stats(input.as_bytes(), Err, |_| {}).ok(),
+            Some(())
+        );
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let alphabet = Alphabet::parse("a-zA-Z0-9").unwrap();
+        assert_eq!(alphabet.len(), 62);
+        assert_eq!(alphabet.bit('a'), Some(0));
+        assert_eq!(alphabet.bit('A'), Some(26));
+        assert_eq!(alphabet.bit('9'), Some(61));
+        assert_eq!(alphabet.bit('!'), None);
+        assert_eq!(alphabet.letter(27), 'B');
+        assert_eq!(Alphabet::parse("a-z").unwrap(), Alphabet::LOWERCASE);
+
+        assert_eq!(Alphabet::parse("a-za"), Err(AlphabetError::Duplicate('a')));
+        assert_eq!(Alphabet::parse("z-a"), Err(AlphabetError::BackwardsRange('z', 'a')));
+        assert_eq!(Alphabet::parse("a-zA-Z0-9!-/"), Err(AlphabetError::TooLong));
+        assert_eq!(Alphabet::parse("aé"), Err(AlphabetError::NotAscii('é')));
+    }
+
+    #[test]
+    fn test_extended_alphabet() {
+        let input = "aBC\nB\n\nXY\nYZ\n";
+        let alphabet = Alphabet::parse("a-zA-Z").unwrap();
+
+        let groups = alphabet.parse_groups(input).unwrap();
+        assert_eq!(groups.iter().map(Group::any_yes).sum::<usize>(), 3 + 3);
+        assert_eq!(groups.iter().map(Group::all_yes).sum::<usize>(), 1 + 1);
+        assert_eq!(alphabet.process_groups(input.as_bytes()).unwrap(), (6, 2));
+        assert_eq!((alphabet.part1(input), alphabet.part2(input)), (6, 2));
+
+        assert_eq!(
+            parse_groups(input),
+            Err(InputError::UnexpectedChar {
+                group: 1,
+                line: 1,
+                col: 2,
+                ch: 'B'
+            })
+        );
+    }
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(SAMPLE), 11);
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(SAMPLE), 6);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert_eq!(part1(""), 0);
+        assert_eq!(part2(""), 0);
+        assert_eq!(part1("\n"), 0);
+        assert_eq!(part2("\n"), 0);
+        for input in ["", "\n"] {
+            assert_eq!(group_stats(input), vec![]);
+            assert_eq!(process_groups(input.as_bytes()).unwrap(), (0, 0));
+        }
+    }
+
+    // the bitmasks should agree with the original set-based solution on the real input
+    #[test]
+    fn test_matches_hashset() {
+        let input = include_str!("input.txt");
+        let sets = |group: &str| -> Vec<HashSet<u8>> { group.lines().map(|line| line.bytes().collect()).collect() };
+
+        let any: usize = input
+            .split("\n\n")
+            .map(|group| HashSet::unions(sets(group)).len())
+            .sum();
+        let all: usize = input
+            .split("\n\n")
+            .map(|group| {
+                sets(group)
+                    .into_iter()
+                    .reduce(|acc, x| acc.intersection(x))
+                    .unwrap_or_default()
+                    .len()
+            })
+            .sum();
+
+        assert_eq!(part1(input), any);
+        assert_eq!(part2(input), all);
+        assert_eq!(process_groups(input.as_bytes()).unwrap(), (any, all));
+    }
+}
@@ -1,102 +1,92 @@
-//use std::{fmt, collections::HashSet};
-use std::fmt;
-// im provides a set of immutable data structures, including HashSet; unions method
-use im::HashSet;
+use anyhow::Context;
+use day06::{Alphabet, GroupStats};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
 
-// im docs: All of these data structures support in-place copy-on-write mutation which
-// means that if you're the sole user of a data structure, you can update it in place
-// without taking the performance hit of making a copy of the data structure before
-// modifying it (this is about an order of magnitude faster than immutable operations,
-// almost as fast as std::collection's mutable data structures.
-//
-// If you aren't the sole owner of an im data structure, you'll get an automatic copy
-// of the node before modifying it. Cloning a data structure becomes a lazy operation.
-
-// get reasonable readability for our output by supplying our own Debug
-pub struct Answers(HashSet<u8>);
-
-impl fmt::Debug for Answers {
-
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for &answer in &self.0 {
-            write!(f, "{}", answer as char)?;
+fn main() -> anyhow::Result<()> {
+    let (mut path, mut lenient, mut show_stats, mut alphabet) = (None, false, false, Alphabet::default());
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lenient" => lenient = true,
+            "--stats" => show_stats = true,
+            // the questions to expect instead of a-z, e.g. `--alphabet a-zA-Z0-9`
+            "--alphabet" => {
+                let spec = args.next().context("--alphabet must be followed by letters like a-zA-Z0-9")?;
+                alphabet = Alphabet::parse(&spec)?;
+            }
+            _ => path = Some(arg),
         }
-        Ok(())
     }
-}
-
-// The standard library lets us compute the union of two HashSets and returns an iterator.
-// We could chain all our iterators and collect the resulting iterator into a single HashSet
 
-fn main() {
-    /*
-    // collect the answers for each person of a group - we don't need a parser to do that much
-    let answers: Vec<_> = include_str!("input.txt")
-        .split("\n\n")
-        .map(|group| {
-            group
-                .lines()
-                .map(|line| Answers(line.as_bytes().iter().copied().collect()))
-                .collect::<Vec<_>>()
-        })
-        .collect();
-
-    let group_answers: Vec<_> = answers
-        .into_iter()
-        .map(|group| Answers(HashSet::unions(group.into_iter().map(|x| x.0))))
-        .collect();
-    */
-
-    // reworked to skip the intermediate step of collecting answers for each person of a group
-    let _group_answers: Vec<_> = include_str!("input.txt")
-        .split("\n\n")
-        .map(|group| {
-            Answers(HashSet::unions(
-                group
-                    .lines()
-                    .map(|line| line.as_bytes().iter().copied().collect()),
-            ))
-        })
-        .collect();
+    // `day06 <path>` (or `-` for stdin) reads the groups from there instead of input.txt, one line at a time.
+    // Either way anything outside the alphabet fails the whole run, unless `--lenient` says to skip it with a warning
+    let input = path.is_none().then_some(include_str!("input.txt"));
+    let reader: Box<dyn BufRead> = match path.as_deref() {
+        None => Box::new(input.unwrap_or_default().as_bytes()),
+        Some("-") => Box::new(std::io::stdin().lock()),
+        Some(path) => Box::new(BufReader::new(File::open(path).with_context(|| format!("couldn't open {}", path))?)),
+    };
+    let on_error = |e| {
+        if lenient {
+            eprintln!("warning: {}", e);
+            Ok(())
+        } else {
+            Err(e)
+        }
+    };
+    // both parts work on a bitmask of answers per person rather than sets, see lib.rs
+    let mut stats: Vec<GroupStats> = Vec::new();
+    alphabet.read_group_stats(reader, on_error, |group| stats.push(group))?;
 
-    //dbg!(&_group_answers[0..5]);
+    if show_stats {
+        print_stats(&stats, &alphabet);
+    }
 
-    // we skip additional intermediate steps to simplify the work to answer to part 1
-    let answer: usize = include_str!("input.txt")
-        .split("\n\n")
-        .map(|group| {
-            HashSet::<u8>::unions(
-                group
-                    .lines()
-                    .map(|line| line.as_bytes().iter().copied().collect()),
-            )
-                .len()
-        })
-        .sum();
+    // input.txt is all in memory, so it can go through part1 and part2 like the tests do; a path or stdin has
+    // already been read, so the answers come from the statistics gathered along the way
+    let (answer, answer2) = match input {
+        Some(input) => (alphabet.part1(input), alphabet.part2(input)),
+        None => (
+            stats.iter().map(|group| group.any_yes).sum(),
+            // keep only the answers to which everyone in the group said yes
+            stats.iter().map(|group| group.all_yes).sum(),
+        ),
+    };
 
     println!("Part 1:");
     println!("  The sum of all questions answered by all the groups is {:?}", answer);
 
-    // note: intersection of empty set with anything is the empty set (avoid fold using HashSet::<u8>::new())
-    // our initial use of "fold" replaced by "reduce" For iterators with at least one element, same as fold() with
-    // the first element of the iterator as the initial accumulator value, folding every subsequent element into it.
-    //let init: HashSet<u8> = (b'a'..=b'z').collect();
-
-    // keep only the answers to which everyone in the group said yes
-    // unwrap_or_default needed for the situation where we are reduce-ing a collection of 0 items
-    let answer2: usize = include_str!("input.txt")
-        .split("\n\n")
-        .map(|group| {
-                group
-                    .lines()
-                    .map(|line| line.as_bytes().iter().copied().collect())
-                    .reduce(|acc: HashSet<u8>, x| acc.intersection(x))
-                    .unwrap_or_default()
-                    .len()
-        })
-        .sum();
-
     println!("Part 2:");
     println!("  The sum of all questions answered by all individuals in each group is {:?}", answer2);
 
+    Ok(())
+}
+
+fn print_stats(stats: &[GroupStats], alphabet: &Alphabet) {
+    let people: usize = stats.iter().map(|group| group.people).sum();
+    let largest = stats.iter().map(|group| group.people).max().unwrap_or_default();
+    println!("Stats:");
+    println!("  {} groups of {} people, the largest has {}", stats.len(), people, largest);
+
+    let mut totals = vec![0usize; alphabet.len()];
+    for group in stats {
+        for (total, &count) in totals.iter_mut().zip(group.per_question.iter()) {
+            *total += count;
+        }
+    }
+    if let Some((question, &count)) = totals.iter().enumerate().max_by_key(|&(_, count)| count) {
+        println!(
+            "  Question {} was answered \"yes\" most often, by {} people",
+            alphabet.letter(question),
+            count
+        );
+    }
+    if let Some((question, &count)) = totals.iter().enumerate().min_by_key(|&(_, count)| count) {
+        println!(
+            "  Question {} was answered \"yes\" least often, by {} people",
+            alphabet.letter(question),
+            count
+        );
+    }
 }
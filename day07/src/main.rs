@@ -1,7 +1,9 @@
 // using multimap to store multiple elements in a thinly wrapped HashMap
+use anyhow::Context;
+use itertools::Itertools;
 use multimap::MultiMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
-use itertools::Itertools;
 
 /// K can contain V.0 of V.1
 type Rules<'a> = MultiMap<BagSpec<'a>, (usize, BagSpec<'a>)>;
@@ -9,7 +11,7 @@ type Rules<'a> = MultiMap<BagSpec<'a>, (usize, BagSpec<'a>)>;
 /// (adjective, color), i.e. ("dark", "orange")
 type BagSpec<'a> = (&'a str, &'a str);
 
-fn parse_rules(input: &str) -> Rules<'_> {
+fn parse_rules(input: &str) -> Result<Rules<'_>, peg::error::ParseError<peg::str::LineCol>> {
     let mut rules: Rules = Default::default();
 
     peg::parser! {
@@ -54,10 +56,196 @@ fn parse_rules(input: &str) -> Rules<'_> {
         }
     }
 
-    parser::root(input, &mut rules).unwrap();
-    rules
+    parser::root(input, &mut rules)?;
+    Ok(rules)
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+enum ParseError {
+    #[error("line {line}, column {column}: expected {expected}")]
+    Syntax {
+        line: usize,
+        column: usize,
+        expected: String,
+    },
+    // a Cycle borrows from the input, so it's kept as its message to outlive it
+    #[error("{0}")]
+    Cycle(String),
+}
+
+const ACYCLIC: &str = "BagRules are checked for cycles when they're parsed";
+
+/// The rules, checked to be free of cycles when parsed, so walking them always comes to an end
+#[derive(Debug)]
+struct BagRules<'a> {
+    rules: Rules<'a>,
+    /// The same rules with the arrows going up, built once for every walk up from a bag
+    reversed: Rules<'a>,
 }
 
+impl<'a> BagRules<'a> {
+    fn parse(input: &'a str) -> Result<Self, ParseError> {
+        let rules = parse_rules(input).map_err(|e| ParseError::Syntax {
+            line: e.location.line,
+            column: e.location.column,
+            expected: e.expected.to_string(),
+        })?;
+        check_acyclic(&rules).map_err(|cycle| ParseError::Cycle(cycle.to_string()))?;
+        let reversed = reverse_graph(&rules);
+        Ok(BagRules { rules, reversed })
+    }
+
+    /// Whether an `outer` bag eventually contains an `inner` one
+    #[cfg(test)]
+    fn can_contain(&self, outer: BagSpec<'a>, inner: BagSpec<'a>) -> bool {
+        contains_with_cache(&self.rules, &outer, &inner, &mut HashMap::new()).expect(ACYCLIC)
+    }
+
+    /// The bags that can eventually contain `needle`, in order
+    fn containers(&self, needle: BagSpec<'a>) -> Vec<BagSpec<'a>> {
+        ancestors(&self.reversed, needle, |_| {})
+            .into_iter()
+            .sorted()
+            .collect()
+    }
+
+    /// How many bags a `root` bag holds, all the way down
+    fn total_contained(&self, root: BagSpec<'a>) -> usize {
+        total_contained(&self.rules, root, &mut HashMap::new()).expect(ACYCLIC)
+    }
+
+    /// The same rules with the arrows going up: each bag maps to the bags that directly contain it
+    #[cfg(test)]
+    fn reverse(&self) -> BagRules<'a> {
+        BagRules {
+            rules: self.reversed.clone(),
+            reversed: self.rules.clone(),
+        }
+    }
+
+    /// Every bag mentioned in the rules, whether it contains anything or not
+    fn bags(&self) -> HashSet<BagSpec<'a>> {
+        self.rules
+            .iter_all()
+            .flat_map(|(&bag, contents)| std::iter::once(bag).chain(contents.iter().map(|&(_, inner)| inner)))
+            .collect()
+    }
+
+    /// Looks up a bag by name, like "shiny gold". If there's no such bag in the rules, the error
+    /// suggests the closest names there are.
+    fn find_bag(&self, name: &str) -> anyhow::Result<BagSpec<'a>> {
+        let bags = self.bags();
+        if let Some(&bag) = bags
+            .iter()
+            .find(|&&(adjective, color)| name.split_once(' ') == Some((adjective, color)))
+        {
+            return Ok(bag);
+        }
+
+        // close enough: a couple of typos away, or sharing the adjective or the color
+        let close: Vec<String> = bags
+            .iter()
+            .map(|&(adjective, color)| format!("{} {}", adjective, color))
+            .map(|candidate| (edit_distance(name, &candidate), candidate))
+            .filter(|(distance, candidate)| {
+                *distance <= 3 || candidate.split(' ').any(|word| name.split(' ').any(|w| w == word))
+            })
+            .sorted()
+            .take(5)
+            .map(|(_, candidate)| candidate)
+            .collect();
+        if close.is_empty() {
+            anyhow::bail!("there are no {:?} bags in the rules", name);
+        }
+        anyhow::bail!(
+            "there are no {:?} bags in the rules, did you mean one of: {}?",
+            name,
+            close.join(", ")
+        )
+    }
+
+    /// One way for `from` to (eventually) contain `to`, see containment_path()
+    fn containment_path(&self, from: BagSpec<'a>, to: BagSpec<'a>) -> Option<Vec<(usize, BagSpec<'a>)>> {
+        containment_path(&self.reversed, from, to)
+    }
+}
+
+/// A loop in the rules: each bag contains the next one, and the last contains the first again
+#[derive(Debug, PartialEq)]
+struct Cycle<'a>(Vec<BagSpec<'a>>);
+
+impl<'a> Cycle<'a> {
+    // `path` ends with a bag that can contain `back_to`, which appears earlier in `path`.
+    // Start the cycle at its smallest bag so the same loop is always reported the same way.
+    fn from_path(path: &[BagSpec<'a>], back_to: BagSpec<'a>) -> Self {
+        let start = path.iter().rposition(|&spec| spec == back_to).unwrap_or(0);
+        let mut bags = path[start..].to_vec();
+        let smallest = bags.iter().position_min().unwrap_or(0);
+        bags.rotate_left(smallest);
+        Cycle(bags)
+    }
+}
+
+impl fmt::Display for Cycle<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bag rules contain a cycle: ")?;
+        for spec in &self.0 {
+            write!(f, "{} {} -> ", spec.0, spec.1)?;
+        }
+        // a cycle always has at least one bag, which contains itself
+        write!(f, "{} {}", self.0[0].0, self.0[0].1)
+    }
+}
+
+impl std::error::Error for Cycle<'_> {}
+
+/// Makes sure no bag can (eventually) contain itself, which would make the answers infinite.
+/// Uses an explicit stack rather than recursion, so long chains of bags are fine.
+fn check_acyclic<'a>(graph: &Rules<'a>) -> Result<(), Cycle<'a>> {
+    enum State {
+        Visiting,
+        Done,
+    }
+    let mut states: HashMap<BagSpec, State> = HashMap::new();
+
+    // visit the bags in order so that the same cycle is found every time
+    for &start in graph.keys().sorted() {
+        if states.contains_key(&start) {
+            continue;
+        }
+        states.insert(start, State::Visiting);
+        // the current path from `start`, with how many of each bag's children were visited
+        let mut path = vec![start];
+        let mut next_child = vec![0];
+
+        while let Some(&node) = path.last() {
+            let depth = path.len() - 1;
+            let children = graph.get_vec(&node).map(Vec::as_slice).unwrap_or_default();
+            match children.get(next_child[depth]) {
+                Some(&(_, child)) => {
+                    next_child[depth] += 1;
+                    match states.get(&child) {
+                        Some(State::Visiting) => return Err(Cycle::from_path(&path, child)),
+                        Some(State::Done) => {}
+                        None => {
+                            states.insert(child, State::Visiting);
+                            path.push(child);
+                            next_child.push(0);
+                        }
+                    }
+                }
+                None => {
+                    states.insert(node, State::Done);
+                    path.pop();
+                    next_child.pop();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[allow(dead_code)]
 // replicate the formatting of the input, for inspection
 struct FormattedRules<'a>(Rules<'a>);
 
@@ -88,14 +276,43 @@ impl fmt::Display for FormattedRules<'_> {
     }
 }
 
+#[cfg(test)]
 // subgraph_contains walks the graph starting from _all the nodes_, it walks the same subgraph multiple times
-fn subgraph_contains(graph: &Rules<'_>, root: &(&str, &str), needle: &(&str, &str)) -> bool {
+fn subgraph_contains<'a>(graph: &Rules<'a>, root: &BagSpec<'a>, needle: &BagSpec<'a>) -> Result<bool, Cycle<'a>> {
+    subgraph_contains_from(graph, &mut vec![*root], needle)
+}
+
+// `path` is the chain of bags that led to the one we're looking in (the last one); coming across
+// any of them again means we're going round in circles, so bail out rather than recurse forever
+#[cfg(test)]
+fn subgraph_contains_from<'a>(
+    graph: &Rules<'a>,
+    path: &mut Vec<BagSpec<'a>>,
+    needle: &BagSpec<'a>,
+) -> Result<bool, Cycle<'a>> {
+    let root = path[path.len() - 1];
+    for &(_, neighbor) in graph.get_vec(&root).into_iter().flatten() {
+        if neighbor == *needle {
+            return Ok(true);
+        }
+        if path.contains(&neighbor) {
+            return Err(Cycle::from_path(path, neighbor));
+        }
+        path.push(neighbor);
+        let found = subgraph_contains_from(graph, path, needle)?;
+        path.pop();
+        if found {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+    /*
     graph
         .get_vec(root)
         .unwrap_or(&Default::default())
         .iter()
         .any(|(_, neighbor)| neighbor == needle || subgraph_contains(graph, neighbor, needle))
-    /*
+
     if let Some(neighbors) = graph.get_vec(root) {
         for (_, neighbor) in neighbors {
             if neighbor == needle || subgraph_contains(graph, neighbor, needle) {
@@ -104,18 +321,82 @@ fn subgraph_contains(graph: &Rules<'_>, root: &(&str, &str), needle: &(&str, &st
         }
     }
     false
-
-    graph
-        .get_vec(root)
-        .map(|v| {
-            v.iter().any(|(_, neighbor)| {
-                neighbor == needle || subgraph_contains(graph, neighbor, needle)
-            })
-        })
-        .unwrap_or_default()
     */
 }
 
+// contains_with_cache() answers the same question as subgraph_contains(), but remembers the answer for every
+// bag it looks in along the way, so walking from all the nodes only walks each subgraph once.
+// `cache` is only valid for a single `needle`. Like total_contained(), it keeps its own stack of the bags it's
+// looking in rather than recursing, so a deep nesting of bags can't overflow the stack.
+#[cfg(test)]
+fn contains_with_cache<'a>(
+    graph: &Rules<'a>,
+    root: &BagSpec<'a>,
+    needle: &BagSpec<'a>,
+    cache: &mut HashMap<BagSpec<'a>, bool>,
+) -> Result<bool, Cycle<'a>> {
+    // the bags we're in the middle of looking in, with which content to look at next
+    let mut path: Vec<(BagSpec<'a>, usize)> = vec![(*root, 0)];
+    // the same bags, for finding cycles without searching the whole path
+    let mut on_path: HashSet<BagSpec<'a>> = HashSet::from([*root]);
+
+    while let Some(&(bag, next)) = path.last() {
+        let contents = graph.get_vec(&bag).map(Vec::as_slice).unwrap_or_default();
+        let found = match (cache.get(&bag), contents.get(next)) {
+            (Some(&found), _) => found,
+            (None, Some(&(_, inner))) if inner == *needle => true,
+            (None, Some(&(_, inner))) => {
+                if on_path.contains(&inner) {
+                    let bags: Vec<_> = path.iter().map(|&(spec, _)| spec).collect();
+                    return Err(Cycle::from_path(&bags, inner));
+                }
+                path.push((inner, 0));
+                on_path.insert(inner);
+                continue;
+            }
+            (None, None) => false,
+        };
+
+        if found {
+            // every bag on the way down to this one contains the needle too
+            for &(spec, _) in &path {
+                cache.insert(spec, true);
+            }
+            return Ok(true);
+        }
+        // nothing in `bag`, on to the parent's next content
+        cache.insert(bag, false);
+        path.pop();
+        on_path.remove(&bag);
+        if let Some(parent) = path.last_mut() {
+            parent.1 += 1;
+        }
+    }
+    Ok(false)
+}
+
+// ancestors() finds every bag that can eventually contain `needle` in a single breadth-first walk up
+// `reversed` (the rules with their arrows going up). Each bag is only visited once, however many
+// paths lead to it, and `visit` is called as it is. The needle itself isn't included, as shiny gold
+// bags are already shiny gold, we're not interested in what they can contain (as per the example).
+fn ancestors<'a>(
+    reversed: &Rules<'a>,
+    needle: BagSpec<'a>,
+    mut visit: impl FnMut(BagSpec<'a>),
+) -> HashSet<BagSpec<'a>> {
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::from([needle]);
+    while let Some(bag) = queue.pop_front() {
+        for &(_, container) in reversed.get_vec(&bag).into_iter().flatten() {
+            if container != needle && visited.insert(container) {
+                visit(container);
+                queue.push_back(container);
+            }
+        }
+    }
+    visited
+}
+
 // as an optimization, we can make the arrows go up
 fn reverse_graph<'a>(graph: &Rules<'a>) -> Rules<'a> {
     graph
@@ -161,20 +442,25 @@ fn walk_subgraph1<'a>(graph: &Rules<'a>, root: &(&str, &str), res: &mut Vec<(&'a
     }
 }
 
-// walk_subgraph2() returns an iterator; leverages Box
+#[cfg(test)]
+// walk_subgraph2() returns an iterator; leverages Box.
+// `path` is the chain of bags that led here, ending with the one to walk from. A bag that's already
+// on the path yields a Cycle error instead of being walked (again, and again...)
 fn walk_subgraph2<'iter, 'elems: 'iter>(
     graph: &'iter Rules<'elems>,
-    root: &(&'iter str, &'iter str),
-) -> Box<dyn Iterator<Item = (&'elems str, &'elems str)> + 'iter> {
-    Box::new(
-        graph
-            .get_vec(root)
-            .into_iter()
-            .flatten()
-            .flat_map(move |&(_, neighbor)| {
-                std::iter::once(neighbor).chain(walk_subgraph2(graph, &neighbor))
-            }),
-    )
+    path: Vec<BagSpec<'elems>>,
+) -> Box<dyn Iterator<Item = Result<BagSpec<'elems>, Cycle<'elems>>> + 'iter> {
+    let root = path[path.len() - 1];
+    Box::new(graph.get_vec(&root).into_iter().flatten().flat_map(
+        move |&(_, neighbor)| -> Box<dyn Iterator<Item = _> + 'iter> {
+            if path.contains(&neighbor) {
+                return Box::new(std::iter::once(Err(Cycle::from_path(&path, neighbor))));
+            }
+            let mut path = path.clone();
+            path.push(neighbor);
+            Box::new(std::iter::once(Ok(neighbor)).chain(walk_subgraph2(graph, path)))
+        },
+    ))
 }
 
 #[allow(dead_code)]
@@ -193,57 +479,441 @@ fn walk_subgraph3<'iter, 'elems: 'iter>(
     )
 }
 
+#[cfg(test)]
 // bag_quantities() reworks the ideas of walk_subgraph3 while multiplying appropriately.
 // We need to multiply stuff together... if every "shiny gold" bag has two "dark red" bags,
 // and those have three "light magenta" bags, then we have 2*3 = 6 "light magenta" bags.
+// Like walk_subgraph2(), `path` ends with the bag to look in, and guards against cycles.
 fn bag_quantities<'iter, 'elems: 'iter>(
     graph: &'iter Rules<'elems>,
-    root: &(&'iter str, &'iter str),
-) -> Box<dyn Iterator<Item = usize> + 'iter> {
-    Box::new(
-        graph
-            .get_vec(root)
-            .into_iter()
-            .flatten()
-            .flat_map(move |&(qt, n)| {
-                std::iter::once(qt).chain(bag_quantities(graph, &n).map(move |x| x * qt))
-            }),
-    )
+    path: Vec<BagSpec<'elems>>,
+) -> Box<dyn Iterator<Item = Result<usize, Cycle<'elems>>> + 'iter> {
+    let root = path[path.len() - 1];
+    Box::new(graph.get_vec(&root).into_iter().flatten().flat_map(
+        move |&(qt, n)| -> Box<dyn Iterator<Item = _> + 'iter> {
+            if path.contains(&n) {
+                return Box::new(std::iter::once(Err(Cycle::from_path(&path, n))));
+            }
+            let mut path = path.clone();
+            path.push(n);
+            Box::new(std::iter::once(Ok(qt)).chain(bag_quantities(graph, path).map(move |x| x.map(|x| x * qt))))
+        },
+    ))
 }
 
-fn main() {
-    let rules = parse_rules(include_str!("input.txt"));
-    //print!("{}", FormattedRules(rules));
+// total_contained() gets to the same answer as bag_quantities() without recursing, so a deep nesting of
+// bags can't overflow the stack. It works bottom-up: a bag's total is only known once its contents'
+// totals are, and those are remembered in `memo` (the number of bags inside each bag) for next time.
+fn total_contained<'a>(
+    graph: &Rules<'a>,
+    root: BagSpec<'a>,
+    memo: &mut HashMap<BagSpec<'a>, usize>,
+) -> Result<usize, Cycle<'a>> {
+    // the bags we're in the middle of adding up: which content to look at next, and the total so far
+    let mut path: Vec<(BagSpec<'a>, usize, usize)> = vec![(root, 0, 0)];
+    // the same bags, for finding cycles without searching the whole path
+    let mut on_path: HashSet<BagSpec<'a>> = HashSet::from([root]);
 
-    let needle = &("shiny", "gold");
-    let colors_that_contain_shiny_gold: Vec<_> = rules
-        .keys()
-        // shiny gold bags are already shiny gold, we're not interested
-        // in what they can contain (as per the example)
-        .filter(|&k| k != needle)
-        .filter(|&k| subgraph_contains(&rules, k, needle))
-        .collect();
-    println!("{:?}", colors_that_contain_shiny_gold);
+    while let Some(&(bag, next, total)) = path.last() {
+        if let Some(&known) = memo.get(&bag) {
+            path.pop();
+            on_path.remove(&bag);
+            if let Some(parent) = path.last_mut() {
+                let (quantity, _) = graph.get_vec(&parent.0).unwrap()[parent.1];
+                parent.1 += 1;
+                parent.2 += quantity * (1 + known);
+            }
+            continue;
+        }
+
+        let contents = graph.get_vec(&bag).map(Vec::as_slice).unwrap_or_default();
+        match contents.get(next) {
+            Some(&(_, inner)) => {
+                if on_path.contains(&inner) {
+                    let bags: Vec<_> = path.iter().map(|&(spec, ..)| spec).collect();
+                    return Err(Cycle::from_path(&bags, inner));
+                }
+                // once `inner` is done (even if it's in `memo` already), it gets added to `bag` above
+                path.push((inner, 0, 0));
+                on_path.insert(inner);
+            }
+            None => {
+                memo.insert(bag, total);
+            }
+        }
+    }
+    Ok(memo[&root])
+}
+
+// the number of single-character edits to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + (ca != cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// containment_path() finds one way for `from` to (eventually) contain `to`: the bags inside one another along
+// the way, with how many of each, ending with `to`. It walks up from `to` through `reversed`, the rules with
+// their arrows going up (see reverse_graph), remembering which bag led to each container until it reaches `from`.
+fn containment_path<'a>(reversed: &Rules<'a>, from: BagSpec<'a>, to: BagSpec<'a>) -> Option<Vec<(usize, BagSpec<'a>)>> {
+    // for every container we've reached: how many of which bag it contains on the way down to `to`
+    let mut came_from: HashMap<BagSpec<'a>, (usize, BagSpec<'a>)> = HashMap::new();
+    let mut stack = vec![to];
+
+    while let Some(bag) = stack.pop() {
+        if bag == from {
+            let mut path = Vec::new();
+            let mut current = from;
+            while current != to {
+                let step = came_from[&current];
+                path.push(step);
+                current = step.1;
+            }
+            return Some(path);
+        }
+        for &(quantity, container) in reversed.get_vec(&bag).into_iter().flatten() {
+            if container != to && !came_from.contains_key(&container) {
+                came_from.insert(container, (quantity, bag));
+                stack.push(container);
+            }
+        }
+    }
+    None
+}
+
+fn format_path(from: BagSpec, path: &[(usize, BagSpec)]) -> String {
+    let mut res = format!("{} {}", from.0, from.1);
+    for (quantity, bag) in path {
+        res += &format!(" -> {} {} {}", quantity, bag.0, bag.1);
+    }
+    res
+}
+
+fn main() -> anyhow::Result<()> {
+    // `day07 [--bag "dark olive"] [path]` answers for another bag, or other rules
+    let (mut bag, mut path, mut explain) = ("shiny gold".to_string(), None, false);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bag" => {
+                bag = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--bag must be followed by a bag like \"shiny gold\""))?
+            }
+            "--explain" => explain = true,
+            _ => path = Some(arg),
+        }
+    }
+    let input = match path {
+        Some(path) => std::fs::read_to_string(&path).with_context(|| format!("couldn't read {}", path))?,
+        None => include_str!("input.txt").to_string(),
+    };
+
+    let rules = BagRules::parse(&input)?;
+    //print!("{}", FormattedRules(rules.0));
+
+    let needle = rules.find_bag(&bag)?;
+    let colors = rules.containers(needle);
+    println!("{:?}", colors);
     println!();
 
-    let rev_rules = reverse_graph(&rules);
-    /*
-    let colors_that_contain_shiny_gold2 = walk_subgraph(&rev_rules, &("shiny", "gold"));
-    println!("  {:?}", colors_that_contain_shiny_gold2);
-    let mut colors_that_contain_shiny_gold3 = Default::default();
-    walk_subgraph1(
-        &rev_rules,
-        &("shiny", "gold"),
-        &mut colors_that_contain_shiny_gold3,
-    );
-    println!("  {:?}", colors_that_contain_shiny_gold3);
-    */
-    let answer1 = walk_subgraph2(&rev_rules, &needle).unique().count();
     println!("Part 1:");
-    println!("  {} colors can contain {:?} bags", answer1, needle);
+    println!("  {} colors can contain {} bags", colors.len(), bag);
+    if explain {
+        for &color in &colors {
+            if let Some(path) = rules.containment_path(color, needle) {
+                println!("    {}", format_path(color, &path));
+            }
+        }
+    }
 
-    let root = ("shiny", "gold");
-    let answer2: usize = bag_quantities(&rules, &root).sum();
+    let answer2 = rules.total_contained(needle);
     println!("Part 2:");
-    println!("  you must buy {} bags to fill a  {:?} bag", answer2, root);
+    println!("  you must buy {} bags to fill a {} bag", answer2, bag);
+
+    Ok(())
+}
+
+#[test]
+fn test_self_loop() {
+    let rules =
+        parse_rules("shiny gold bags contain 1 shiny gold bag.\nfaded blue bags contain 2 shiny gold bags.").unwrap();
+    let cycle = Cycle(vec![("shiny", "gold")]);
+    assert_eq!(check_acyclic(&rules), Err(Cycle(vec![("shiny", "gold")])));
+    assert_eq!(cycle.to_string(), "bag rules contain a cycle: shiny gold -> shiny gold");
+
+    // without validation the walks stop at the cycle rather than overflowing the stack
+    assert_eq!(
+        contains_with_cache(&rules, &("faded", "blue"), &("dotted", "black"), &mut HashMap::new()),
+        Err(Cycle(cycle.0.clone()))
+    );
+    assert_eq!(
+        subgraph_contains(&rules, &("faded", "blue"), &("dotted", "black")),
+        Err(cycle)
+    );
+    assert!(bag_quantities(&rules, vec![("shiny", "gold")]).any(|qt| qt.is_err()));
+}
+
+#[test]
+fn test_three_bag_cycle() {
+    let rules = parse_rules(indoc::indoc! {"
+        light red bags contain 1 bright white bag, 2 muted yellow bags.
+        muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+        shiny gold bags contain 1 light red bag.
+        faded blue bags contain no other bags.
+    "})
+    .unwrap();
+    let cycle = Cycle(vec![("light", "red"), ("muted", "yellow"), ("shiny", "gold")]);
+    assert_eq!(check_acyclic(&rules), Err(Cycle(cycle.0.clone())));
+    assert_eq!(
+        cycle.to_string(),
+        "bag rules contain a cycle: light red -> muted yellow -> shiny gold -> light red"
+    );
+
+    assert!(subgraph_contains(&rules, &("shiny", "gold"), &("dotted", "black")).is_err());
+    let reversed = reverse_graph(&rules);
+    assert!(walk_subgraph2(&reversed, vec![("faded", "blue")]).any(|spec| spec.is_err()));
+    assert!(bag_quantities(&rules, vec![("shiny", "gold")]).any(|qt| qt.is_err()));
+}
+
+#[test]
+fn test_acyclic() {
+    let rules = parse_rules(include_str!("sample.txt")).unwrap();
+    assert_eq!(check_acyclic(&rules), Ok(()));
+    assert_eq!(
+        bag_quantities(&rules, vec![("shiny", "gold")]).sum::<Result<usize, _>>(),
+        Ok(32)
+    );
+}
+
+#[test]
+fn test_contains_with_cache() {
+    let rules = parse_rules(include_str!("sample.txt")).unwrap();
+    let needle = ("shiny", "gold");
+    let mut cache = HashMap::new();
+    for k in rules.keys() {
+        assert_eq!(
+            contains_with_cache(&rules, k, &needle, &mut cache),
+            subgraph_contains(&rules, k, &needle)
+        );
+    }
+    assert_eq!(cache.values().filter(|&&found| found).count(), 4);
+}
+
+#[test]
+fn test_contains_with_cache_long_chain() {
+    // bag 0 contains bag 1, which contains bag 2... all the way to bag 999
+    let input: String = (0..999)
+        .map(|i| format!("bag{} plain bags contain 1 bag{} plain bag.\n", i, i + 1))
+        .collect();
+    let rules = parse_rules(&input).unwrap();
+    let needle = ("bag999", "plain");
+
+    let mut cache = HashMap::new();
+    let mut count = 0;
+    for k in rules.keys() {
+        if contains_with_cache(&rules, k, &needle, &mut cache).unwrap() {
+            count += 1;
+        }
+    }
+    assert_eq!(count, 999);
+    let mut cache = HashMap::new();
+    assert_eq!(
+        contains_with_cache(&rules, &("bag500", "plain"), &("bag10", "plain"), &mut cache),
+        Ok(false)
+    );
+}
+
+#[test]
+fn test_contains_with_cache_deep_nesting() {
+    // far deeper than the recursive version could go
+    let depth = 100_000;
+    let input: String = (0..depth)
+        .map(|i| format!("bag{} plain bags contain 1 bag{} plain bag.\n", i, i + 1))
+        .collect();
+    let rules = parse_rules(&input).unwrap();
+    let needle = ("bag100000", "plain");
+
+    let mut cache = HashMap::new();
+    assert_eq!(contains_with_cache(&rules, &("bag0", "plain"), &needle, &mut cache), Ok(true));
+    assert_eq!(cache.len(), depth);
+    assert!(cache.values().all(|&found| found));
+
+    let mut cache = HashMap::new();
+    assert_eq!(
+        contains_with_cache(&rules, &("bag0", "plain"), &("nowhere", "plain"), &mut cache),
+        Ok(false)
+    );
+    assert_eq!(cache.len(), depth + 1);
+}
+
+#[test]
+fn test_total_contained() {
+    let rules = parse_rules(include_str!("sample.txt")).unwrap();
+    let mut memo = HashMap::new();
+    assert_eq!(total_contained(&rules, ("shiny", "gold"), &mut memo), Ok(32));
+    assert_eq!(memo[&("dark", "olive")], 7);
+    assert_eq!(memo[&("faded", "blue")], 0);
+    // asking again only needs the memo
+    assert_eq!(total_contained(&rules, ("vibrant", "plum"), &mut memo), Ok(11));
+
+    let rules = parse_rules(include_str!("sample2.txt")).unwrap();
+    assert_eq!(total_contained(&rules, ("shiny", "gold"), &mut HashMap::new()), Ok(126));
+    assert_eq!(
+        bag_quantities(&rules, vec![("shiny", "gold")]).sum::<Result<usize, _>>(),
+        Ok(126)
+    );
+
+    let rules =
+        parse_rules("shiny gold bags contain 2 dark red bags.\ndark red bags contain 1 shiny gold bag.").unwrap();
+    assert_eq!(
+        total_contained(&rules, ("shiny", "gold"), &mut HashMap::new()),
+        Err(Cycle(vec![("dark", "red"), ("shiny", "gold")]))
+    );
+}
+
+#[test]
+fn test_total_contained_deep_nesting() {
+    // bag 0 contains bag 1, which contains bag 2... far deeper than bag_quantities() can recurse
+    let depth = 100_000;
+    let input: String = (0..depth)
+        .map(|i| format!("bag{} plain bags contain 1 bag{} plain bag.\n", i, i + 1))
+        .collect();
+    let rules = parse_rules(&input).unwrap();
+    assert_eq!(check_acyclic(&rules), Ok(()));
+    assert_eq!(
+        total_contained(&rules, ("bag0", "plain"), &mut HashMap::new()),
+        Ok(depth)
+    );
+}
+
+#[test]
+fn test_other_bag() {
+    let rules = BagRules::parse(include_str!("sample.txt")).unwrap();
+
+    let needle = rules.find_bag("bright white").unwrap();
+    assert_eq!(needle, ("bright", "white"));
+    assert_eq!(rules.containers(needle), vec![("dark", "orange"), ("light", "red")]);
+    assert_eq!(rules.total_contained(needle), 1 + 32);
+
+    // bags that don't contain anything are still bags
+    let needle = rules.find_bag("dotted black").unwrap();
+    assert_eq!(rules.containers(needle).len(), 7);
+    assert_eq!(rules.total_contained(needle), 0);
+
+    let err = rules.find_bag("shiny golden").unwrap_err().to_string();
+    assert!(err.contains("did you mean one of: shiny gold"), "{}", err);
+    assert_eq!(
+        rules.find_bag("plain purple").unwrap_err().to_string(),
+        "there are no \"plain purple\" bags in the rules"
+    );
+}
+
+#[test]
+fn test_bag_rules() {
+    let rules = BagRules::parse(include_str!("sample.txt")).unwrap();
+    assert!(rules.can_contain(("light", "red"), ("shiny", "gold")));
+    assert!(rules.can_contain(("shiny", "gold"), ("faded", "blue")));
+    assert!(!rules.can_contain(("shiny", "gold"), ("light", "red")));
+    assert_eq!(rules.total_contained(("shiny", "gold")), 32);
+    assert_eq!(rules.bags().len(), 9);
+
+    let reversed = rules.reverse();
+    assert!(reversed.can_contain(("shiny", "gold"), ("light", "red")));
+    // everything light red holds, all the way down
+    assert_eq!(reversed.containers(("light", "red")).len(), 7);
+
+    assert_eq!(
+        BagRules::parse(include_str!("sample2.txt"))
+            .unwrap()
+            .total_contained(("shiny", "gold")),
+        126
+    );
+}
+
+#[test]
+fn test_bag_rules_malformed() {
+    let input = indoc::indoc! {"
+        light red bags contain 1 bright white bag, 2 muted yellow bags.
+        bright white bags contain 1 shiny gold bag
+        shiny gold bags contain no other bags.
+    "};
+    match BagRules::parse(input) {
+        Err(ParseError::Syntax { line, .. }) => assert_eq!(line, 2),
+        other => panic!("expected a syntax error, got {:?}", other),
+    }
+
+    assert_eq!(
+        BagRules::parse("shiny gold bags contain 1 shiny gold bag.").unwrap_err(),
+        ParseError::Cycle("bag rules contain a cycle: shiny gold -> shiny gold".to_string())
+    );
+}
+
+#[test]
+fn test_containment_path() {
+    let rules = parse_rules(include_str!("sample.txt")).unwrap();
+    let reversed = reverse_graph(&rules);
+    let needle = ("shiny", "gold");
+
+    // light red holds shiny gold through either bright white or muted yellow, depending on which is found first
+    let path = containment_path(&reversed, ("light", "red"), needle).unwrap();
+    assert_eq!(path.len(), 2);
+    assert_eq!(path[1].1, needle);
+    assert!(path == [(1, ("bright", "white")), (1, needle)] || path == [(2, ("muted", "yellow")), (2, needle)]);
+
+    assert_eq!(
+        containment_path(&reversed, ("bright", "white"), needle),
+        Some(vec![(1, needle)])
+    );
+    assert_eq!(
+        format_path(("bright", "white"), &[(1, needle)]),
+        "bright white -> 1 shiny gold"
+    );
+    // shiny gold is inside dark olive, not the other way around
+    assert_eq!(containment_path(&reversed, ("dark", "olive"), needle), None);
+    assert_eq!(containment_path(&reversed, ("faded", "blue"), needle), None);
+}
+
+#[test]
+fn test_ancestors_diamond() {
+    // two ways from the top of the diamond down to the needle, and a bag on top of that
+    let rules = BagRules::parse(indoc::indoc! {"
+        wrapping paper bags contain 1 top corner bag.
+        top corner bags contain 1 left corner bag, 2 right corner bags.
+        left corner bags contain 3 bottom corner bags.
+        right corner bags contain 4 bottom corner bags.
+        bottom corner bags contain no other bags.
+    "})
+    .unwrap();
+    let mut visits: HashMap<BagSpec, usize> = HashMap::new();
+    let found = ancestors(&rules.reversed, ("bottom", "corner"), |bag| {
+        *visits.entry(bag).or_default() += 1
+    });
+
+    let expected: HashSet<_> = [
+        ("left", "corner"),
+        ("right", "corner"),
+        ("top", "corner"),
+        ("wrapping", "paper"),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(found, expected);
+    assert_eq!(visits.len(), 4);
+    assert!(visits.values().all(|&count| count == 1), "{:?}", visits);
+
+    assert_eq!(
+        rules.containers(("right", "corner")),
+        vec![("top", "corner"), ("wrapping", "paper")]
+    );
+    assert!(rules.containers(("wrapping", "paper")).is_empty());
 }
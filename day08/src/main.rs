@@ -1,23 +1,39 @@
 use std::convert::TryInto;
-use std::collections::HashSet;
-use itertools::Itertools;
+use std::fmt;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum InstructionKind {
     Nop,
     Acc,
     Jmp,
 }
 
-#[derive(Debug, Clone, Copy)]
+impl fmt::Display for InstructionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InstructionKind::Nop => "nop",
+            InstructionKind::Acc => "acc",
+            InstructionKind::Jmp => "jmp",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Instruction {
     kind: InstructionKind,
     operand: isize,
 }
 
+/// The canonical listing form, e.g. `jmp +4` or `acc -99`: the operand's sign is always printed
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:+}", self.kind, self.operand)
+    }
+}
+
 type Program = Vec<Instruction>;
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 struct State {
     /// Program counter (instruction pointer)
     pc: usize,
@@ -26,9 +42,12 @@ struct State {
 }
 
 impl State {
-    fn next(self, program: &Program) -> Self {
-        let ins = program[self.pc];
-        match ins.kind {
+    /// Executes the instruction at `pc`. Fails if there is no instruction there, or if it's a jump that would
+    /// take the program counter out of the program (landing exactly one past the end is fine: that's how a
+    /// program terminates).
+    fn next(self, program: &Program) -> Result<Self, Fault> {
+        let ins = *program.get(self.pc).ok_or(Fault::PcOverrun { pc: self.pc })?;
+        Ok(match ins.kind {
             InstructionKind::Nop => Self {
                 pc: self.pc + 1,
                 ..self
@@ -37,121 +56,334 @@ impl State {
                 pc: self.pc + 1,
                 acc: self.acc + ins.operand,
             },
-            InstructionKind::Jmp => Self {
-                pc: (self.pc as isize + ins.operand).try_into().unwrap(),
-                ..self
-            },
+            InstructionKind::Jmp => {
+                let target = self.pc as isize + ins.operand;
+                Self {
+                    pc: target
+                        .try_into()
+                        .ok()
+                        .filter(|&pc| pc <= program.len())
+                        .ok_or(Fault::JumpOutOfBounds { from: self.pc, target })?,
+                    ..self
+                }
+            }
+        })
+    }
+    /// Like `next`, but returns `None` once the program has terminated
+    fn next_option(self, program: &Program) -> Result<Option<Self>, Fault> {
+        if self.pc == program.len() {
+            return Ok(None);
         }
+        self.next(program).map(Some)
     }
-    fn next_option(self, program: &Program) -> Option<Self> {
-        if !(0..program.len()).contains(&self.pc) {
-            return None;
+}
+
+/// Why a program stopped without terminating normally
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fault {
+    /// A `jmp` whose target lies before the first instruction or more than one past the last
+    JumpOutOfBounds { from: usize, target: isize },
+    /// The program counter ended up past the end of the program, but not exactly at the end
+    PcOverrun { pc: usize },
+}
+
+/// How a call to `Machine::run` ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    /// The program counter landed exactly one past the last instruction
+    Terminated {
+        acc: isize,
+    },
+    /// The instruction at `pc` was about to run for a second time, with `acc` in the accumulator
+    InfiniteLoop {
+        pc: usize,
+        acc: isize,
+    },
+    Fault(Fault),
+    /// `Machine::run_traced` gave up after its step limit, leaving the machine at `pc` with `acc` in the accumulator
+    StepLimit {
+        pc: usize,
+        acc: isize,
+    },
+}
+
+/// A program together with the state it is executing in
+#[derive(Debug, Clone)]
+struct Machine {
+    program: Program,
+    state: State,
+    /// Which instructions have been executed so far
+    seen: Vec<bool>,
+    /// How iterating came to an end, once it has
+    outcome: Option<Outcome>,
+}
+
+impl Machine {
+    fn new(program: Program) -> Self {
+        Self {
+            seen: vec![false; program.len()],
+            program,
+            state: State::default(),
+            outcome: None,
+        }
+    }
+
+    /// The state the machine is currently in, to be handed back to `restore` later
+    #[cfg(test)]
+    fn snapshot(&self) -> State {
+        self.state
+    }
+
+    /// Puts the machine back in `state`, and forgets which instructions have been executed so far, so that
+    /// the same machine can be run again, possibly after changing its program.
+    #[cfg(test)]
+    fn restore(&mut self, state: State) {
+        self.state = state;
+        self.seen.iter_mut().for_each(|seen| *seen = false);
+        self.outcome = None;
+    }
+
+    /// The state after executing the current instruction, or why the machine can't go on: the program has
+    /// terminated, the instruction at `pc` has already been executed, or it faults.
+    fn step(&self) -> Result<State, Outcome> {
+        let State { pc, acc } = self.state;
+        if self.seen.get(pc) == Some(&true) {
+            return Err(Outcome::InfiniteLoop { pc, acc });
         }
+        match self.state.next_option(&self.program) {
+            Ok(Some(next)) => Ok(next),
+            Ok(None) => Err(Outcome::Terminated { acc }),
+            Err(fault) => Err(Outcome::Fault(fault)),
+        }
+    }
 
-        let ins = program[self.pc];
-        Some(match ins.kind {
-            InstructionKind::Nop => Self {
-                pc: self.pc + 1,
-                ..self
-            },
-            InstructionKind::Acc => Self {
-                pc: self.pc + 1,
-                acc: self.acc + ins.operand,
-            },
-            InstructionKind::Jmp => Self {
-                pc: (self.pc as isize + ins.operand).try_into().unwrap(),
-                ..self
-            },
-        })
+    /// Runs the program from its current state until it terminates, is about to execute an instruction
+    /// for the second time, or faults.
+    fn run(&mut self) -> Outcome {
+        self.run_traced(usize::MAX, |_, _| {})
+    }
+
+    /// Like `run`, but calls `on_step` for each instruction the machine comes to, including the one it stops
+    /// at for having executed it before, and stops with `Outcome::StepLimit` once `limit` instructions have been
+    /// executed.
+    fn run_traced(&mut self, limit: usize, mut on_step: impl FnMut(&State, &Instruction)) -> Outcome {
+        let mut steps = 0;
+        loop {
+            // the limit only kicks in if the program would otherwise keep going
+            if steps == limit && self.step().is_ok() {
+                let State { pc, acc } = self.state;
+                return Outcome::StepLimit { pc, acc };
+            }
+            match self.next() {
+                Some(state) => {
+                    if let Some(ins) = self.program.get(state.pc) {
+                        on_step(&state, ins);
+                    }
+                    steps += 1;
+                }
+                None => return self.outcome.expect("the machine only stops iterating once it has an outcome"),
+            }
+        }
     }
 }
 
-// parse_program() implements a quick manual parser
-fn parse_program(input: &str) -> Program {
-    input
-        .lines()
-        .map(|l| {
-            let mut tokens = l.split(' ');
-            Instruction {
-                kind: match tokens.next() {
-                    Some(tok) => match tok {
-                        "nop" => InstructionKind::Nop,
-                        "acc" => InstructionKind::Acc,
-                        "jmp" => InstructionKind::Jmp,
-                        _ => panic!("unknown instruction kind {}", tok)
-                    },
-                    None => panic!("for line {}, expected instruction kind", l),
-                },
-                operand: match tokens.next() {
-                    Some(tok) => tok.parse().unwrap(),
-                    None => panic!("for line {}, expected operand", l),
-                },
+/// Steps through the program one instruction at a time, yielding each state before its instruction is executed.
+/// The last state yielded is the one where the program has terminated, where it is about to execute an
+/// instruction for the second time, or whose instruction faults.
+impl Iterator for Machine {
+    type Item = State;
+
+    fn next(&mut self) -> Option<State> {
+        if self.outcome.is_some() {
+            return None;
+        }
+        let state = self.state;
+        match self.step() {
+            Ok(next) => {
+                self.seen[state.pc] = true;
+                self.state = next;
             }
-        })
-        .collect()
+            Err(outcome) => self.outcome = Some(outcome),
+        }
+        Some(state)
+    }
 }
 
-fn main() {
-    let program = parse_program(include_str!("input.txt"));
-    //dbg!(program);
+/// A problem with one line of a program listing. Line numbers are 1-based.
+#[derive(thiserror::Error, Debug, PartialEq)]
+enum ParseError {
+    #[error("line {line}: expected an instruction")]
+    MissingInstruction { line: usize },
+    #[error("line {line}: unknown instruction {token:?}")]
+    UnknownInstruction { line: usize, token: String },
+    #[error("line {line}: expected an operand")]
+    MissingOperand { line: usize },
+    #[error("line {line}: invalid operand {token:?}")]
+    InvalidOperand { line: usize, token: String },
+    #[error("line {line}: labelled as address {found}, but it is at address {expected}")]
+    WrongAddress { line: usize, expected: usize, found: usize },
+    #[error("line {line}: unexpected {token:?} after the operand")]
+    TrailingToken { line: usize, token: String },
+}
 
-    //let mut state: State = Default::default();
-    //dbg!("initial state", state);
+// parse_program() implements a quick manual parser. Rather than stopping at the first bad line, it collects
+// every error so a corrupted input reports everything that's wrong with it at once.
+fn parse_program(input: &str) -> Result<Program, Vec<ParseError>> {
+    let mut program = Vec::new();
+    let mut errors = Vec::new();
+    for (index, l) in input.lines().enumerate() {
+        match parse_instruction(index + 1, program.len() + errors.len(), l) {
+            Ok(ins) => program.push(ins),
+            Err(e) => errors.push(e),
+        }
+    }
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        Err(errors)
+    }
+}
 
-    /*
-    for _ in 0..5 {
-        println!("will execute {:?}", program[state.pc]);
-        state = state.next(&program);
-        dbg!(state);
+// parse_instruction() also accepts the `address:` labels written by dump(), as long as they are right.
+fn parse_instruction(line: usize, address: usize, l: &str) -> Result<Instruction, ParseError> {
+    let mut tokens = l.split_whitespace().peekable();
+    if let Some(label) = tokens.peek().and_then(|tok| tok.strip_suffix(':')) {
+        match label.parse() {
+            Ok(found) if found == address => {}
+            Ok(found) => {
+                return Err(ParseError::WrongAddress {
+                    line,
+                    expected: address,
+                    found,
+                })
+            }
+            Err(_) => {
+                return Err(ParseError::UnknownInstruction {
+                    line,
+                    token: label.to_string(),
+                })
+            }
+        }
+        tokens.next();
+    }
+    let kind = match tokens.next() {
+        Some("nop") => InstructionKind::Nop,
+        Some("acc") => InstructionKind::Acc,
+        Some("jmp") => InstructionKind::Jmp,
+        Some(tok) => {
+            return Err(ParseError::UnknownInstruction {
+                line,
+                token: tok.to_string(),
+            })
+        }
+        None => return Err(ParseError::MissingInstruction { line }),
+    };
+    let operand = match tokens.next() {
+        Some(tok) => parse_operand(tok).ok_or_else(|| ParseError::InvalidOperand {
+            line,
+            token: tok.to_string(),
+        })?,
+        None => return Err(ParseError::MissingOperand { line }),
+    };
+    if let Some(tok) = tokens.next() {
+        return Err(ParseError::TrailingToken {
+            line,
+            token: tok.to_string(),
+        });
     }
-    */
+    Ok(Instruction { kind, operand })
+}
 
-    /*
-    let iter = std::iter::from_fn(|| {
-        state = state.next(&program);
-        Some(state)
-    });
-    */
-    let mut iter = itertools::iterate(State::default(), |s| s.next(&program));
-    //dbg!(iter.take(5).collect::<Vec<_>>());
+// parse_operand() accepts a decimal number with an optional sign, either `+` or `-`. The puzzle input always
+// has one, but it may be left out for positive numbers.
+fn parse_operand(tok: &str) -> Option<isize> {
+    let (negative, digits) = match tok.as_bytes().first()? {
+        b'+' => (false, &tok[1..]),
+        b'-' => (true, &tok[1..]),
+        _ => (false, tok),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let magnitude: isize = digits.parse().ok()?;
+    Some(if negative { -magnitude } else { magnitude })
+}
 
-    // We need to determine when we run an instruction for the second time, so we maintain a hashset of
-    // all the instructions' positions we have already executed. Whenever HashSet::insert returns false
-    // (it did have this value present), we stop and return what's in the accumulator.
-    let mut set: HashSet<usize> = Default::default();
-    let answer = iter.find(|state| !set.insert(state.pc)).unwrap();
+// dump() lists the program one instruction per line, each preceded by its address
+fn dump(program: &Program) -> String {
+    let width = program.len().saturating_sub(1).to_string().len();
+    program
+        .iter()
+        .enumerate()
+        .map(|(address, ins)| format!("{:>width$}: {}\n", address, ins, width = width))
+        .collect()
+}
 
-    println!("Part 1:");
-    println!(
-        "  Before executing {} a second time, the accumulator was {}",
-        answer.pc, answer.acc
-    );
+// trace_step() prints an instruction about to be executed, along with the accumulator, to stderr
+fn trace_step(state: &State, ins: &Instruction) {
+    eprintln!("{:>4}: {:<8} acc={}", state.pc, ins.to_string(), state.acc);
+}
 
-    /*
-    let num_jmp_and_nop = program
-        .iter()
-        .filter(|i| matches!(i.kind, InstructionKind::Jmp | InstructionKind::Nop))
-        .count();
-    dbg!(num_jmp_and_nop);
-    */
+fn main() {
+    let mut disasm = false;
+    let mut trace = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--disasm" => disasm = true,
+            "--trace" => trace = true,
+            _ => {
+                eprintln!("usage: day08 [--disasm] [--trace]");
+                std::process::exit(2);
+            }
+        }
+    }
 
-    println!("Part 2:");
-    // This line was run initially, giving variant 196 terminated!... then the following three lines were run
-    //find_variant(&program);
-    let mut program = parse_program(include_str!("input.txt"));
-    flip_kind(&mut program[196].kind);
-    dbg!(eval(&program));
+    let program = match parse_program(include_str!("input.txt")) {
+        Ok(program) => program,
+        Err(errors) => {
+            for e in errors {
+                eprintln!("{}", e);
+            }
+            std::process::exit(1);
+        }
+    };
+    if disasm {
+        print!("{}", dump(&program));
+        return;
+    }
 
-}
+    // The machine remembers every instruction's position it has already executed, and stops as soon as it
+    // is about to run one of them a second time, reporting what's in the accumulator.
+    println!("Part 1:");
+    let mut machine = Machine::new(program.clone());
+    let outcome = if trace {
+        machine.run_traced(usize::MAX, trace_step)
+    } else {
+        machine.run()
+    };
+    match outcome {
+        Outcome::InfiniteLoop { pc, acc } => {
+            println!("  Before executing {} a second time, the accumulator was {}", pc, acc)
+        }
+        outcome => panic!("expected the program to loop, but got {:?}", outcome),
+    }
 
-// we've identified the statement and flipped it. We iterate over the program using
-fn eval(program: &Program) -> Option<isize> {
-    itertools::iterate(Some(State::default()), |state| {
-        state.and_then(|state| state.next_option(program))
-    })
-        .while_some()
-        .last()
-        .map(|s| s.acc)
+    println!("Part 2:");
+    match fix_program_fast(&program) {
+        Some((index, acc)) => {
+            if trace {
+                let mut fixed = program.clone();
+                flip_kind(&mut fixed[index].kind);
+                eprintln!("fixed program:");
+                Machine::new(fixed).run_traced(usize::MAX, trace_step);
+            }
+            println!(
+                "  Flipping instruction {} makes the program terminate, the accumulator was {}",
+                index, acc
+            )
+        }
+        None => panic!("no single jmp/nop flip makes the program terminate"),
+    }
 }
 
 fn flip_kind(kind: &mut InstructionKind) {
@@ -162,33 +394,345 @@ fn flip_kind(kind: &mut InstructionKind) {
     };
 }
 
-fn find_variant(program: &Program) {
-    // filter_map + map generates all possible programs, and the second map evaluates
-    // each program by iterating over its state as we keep evaluating instructions.
-    let mut variants: Vec<_> = program
+// fix_program() tries flipping every jmp and nop in turn, running each variant until it terminates, loops or
+// faults.
+// Returns the index of the flipped instruction and the accumulator of the first variant that terminates.
+// That's O(n²) in the worst case; it's kept around to check fix_program_fast() against.
+#[cfg(test)]
+fn fix_program(program: &Program) -> Option<(usize, isize)> {
+    // a single machine is reused for every variant, flipping the instruction back after each run
+    let mut machine = Machine::new(program.clone());
+    let start = machine.snapshot();
+    program
         .iter()
         .enumerate()
-        .filter_map(|(index, ins)| match ins.kind {
-            InstructionKind::Jmp | InstructionKind::Nop => Some(index),
-            _ => None,
-        })
-        .map(|i| {
-            let mut variant = program.clone();
-            flip_kind(&mut variant[i].kind);
-            (i, variant)
+        .filter(|(_, ins)| matches!(ins.kind, InstructionKind::Jmp | InstructionKind::Nop))
+        .find_map(|(index, _)| {
+            machine.restore(start);
+            flip_kind(&mut machine.program[index].kind);
+            let outcome = machine.run();
+            flip_kind(&mut machine.program[index].kind);
+            match outcome {
+                Outcome::Terminated { acc } => Some((index, acc)),
+                _ => None,
+            }
         })
-        .map(|(index, variant)| {
-            itertools::iterate(Some(State::default()), move |state| {
-                state
-                    .unwrap_or_else(|| panic!("variant {} terminated!", index))
-                    .next_option(&variant)
+}
+
+// successor() is where the instruction at `pc` sends the program counter, without checking that it's in range
+fn successor(pc: usize, ins: Instruction) -> isize {
+    match ins.kind {
+        InstructionKind::Nop | InstructionKind::Acc => pc as isize + 1,
+        InstructionKind::Jmp => pc as isize + ins.operand,
+    }
+}
+
+// reaches_end() finds every pc from which the unmodified program goes on to terminate, by walking the "next pc"
+// graph backwards from the end of the program. Index `program.len()` is the end itself.
+fn reaches_end(program: &Program) -> Vec<bool> {
+    let len = program.len();
+    let mut preds = vec![Vec::new(); len + 1];
+    for (pc, &ins) in program.iter().enumerate() {
+        match usize::try_from(successor(pc, ins)) {
+            Ok(target) if target <= len => preds[target].push(pc),
+            _ => {}
+        }
+    }
+    let mut reached = vec![false; len + 1];
+    reached[len] = true;
+    let mut stack = vec![len];
+    while let Some(pc) = stack.pop() {
+        for &pred in &preds[pc] {
+            if !std::mem::replace(&mut reached[pred], true) {
+                stack.push(pred);
+            }
+        }
+    }
+    reached
+}
+
+// fix_program_fast() gives the same answer as fix_program() in linear time. Only instructions the program
+// actually executes can make a difference when flipped, and once one of them has been flipped, the rest of the
+// run follows the unmodified program (it can't come back through the flipped instruction without looping).
+// So we walk the original execution once, and flip the first jmp or nop whose new successor is known to reach
+// the end. Only that fixed program is simulated, to get the accumulator. Returns None if no flip helps, or if
+// the program already terminates as it is.
+fn fix_program_fast(program: &Program) -> Option<(usize, isize)> {
+    let reached = reaches_end(program);
+    if reached[0] {
+        return None;
+    }
+    let mut seen = vec![false; program.len()];
+    let mut state = State::default();
+    while state.pc < program.len() && !std::mem::replace(&mut seen[state.pc], true) {
+        let mut flipped = program[state.pc];
+        flip_kind(&mut flipped.kind);
+        if flipped.kind != program[state.pc].kind {
+            let target = successor(state.pc, flipped);
+            if usize::try_from(target).is_ok_and(|target| reached.get(target) == Some(&true)) {
+                let mut fixed = program.clone();
+                fixed[state.pc] = flipped;
+                return match Machine::new(fixed).run() {
+                    Outcome::Terminated { acc } => Some((state.pc, acc)),
+                    outcome => unreachable!("fixed program didn't terminate: {:?}", outcome),
+                };
+            }
+        }
+        state = state.next(program).ok()?;
+    }
+    None
+}
+
+#[test]
+fn test_example_loops() {
+    let program = parse_program(include_str!("sample.txt")).unwrap();
+    assert_eq!(Machine::new(program).run(), Outcome::InfiniteLoop { pc: 1, acc: 5 });
+}
+
+#[test]
+fn test_example_terminates() {
+    let mut program = parse_program(include_str!("sample.txt")).unwrap();
+    flip_kind(&mut program[7].kind);
+    assert_eq!(Machine::new(program.clone()).run(), Outcome::Terminated { acc: 8 });
+
+    let program = parse_program(include_str!("sample.txt")).unwrap();
+    assert_eq!(fix_program(&program), Some((7, 8)));
+    assert_eq!(fix_program_fast(&program), Some((7, 8)));
+}
+
+#[test]
+fn test_faults() {
+    let program = parse_program("nop +0\njmp -2\n").unwrap();
+    assert_eq!(
+        Machine::new(program).run(),
+        Outcome::Fault(Fault::JumpOutOfBounds { from: 1, target: -1 })
+    );
+
+    let mut machine = Machine::new(parse_program("nop +0\n").unwrap());
+    machine.state.pc = 3;
+    assert_eq!(machine.run(), Outcome::Fault(Fault::PcOverrun { pc: 3 }));
+}
+
+#[test]
+fn test_parse_errors() {
+    let errors = parse_program("nop +0\nacc +1\nmul +3\njmp -3\nacc 9x\n").unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            ParseError::UnknownInstruction {
+                line: 3,
+                token: "mul".to_string()
+            },
+            ParseError::InvalidOperand {
+                line: 5,
+                token: "9x".to_string()
+            },
+        ]
+    );
+
+    let errors = parse_program("jmp\n\nacc +1 +2\nnop +-1\n").unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            ParseError::MissingOperand { line: 1 },
+            ParseError::MissingInstruction { line: 2 },
+            ParseError::TrailingToken {
+                line: 3,
+                token: "+2".to_string()
+            },
+            ParseError::InvalidOperand {
+                line: 4,
+                token: "+-1".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_parse_operand_sign() {
+    assert_eq!(parse_operand("+4"), Some(4));
+    assert_eq!(parse_operand("4"), Some(4));
+    assert_eq!(parse_operand("-99"), Some(-99));
+    assert_eq!(parse_operand("+0"), Some(0));
+    assert_eq!(parse_operand("+"), None);
+    assert_eq!(parse_operand("--1"), None);
+    assert_eq!(parse_operand(""), None);
+}
+
+#[test]
+fn test_display() {
+    let program = parse_program("nop +0\njmp 4\nacc -99\n").unwrap();
+    let text: Vec<_> = program.iter().map(|ins| ins.to_string()).collect();
+    assert_eq!(text, vec!["nop +0", "jmp +4", "acc -99"]);
+}
+
+#[test]
+fn test_dump_round_trip() {
+    let program = parse_program(include_str!("sample.txt")).unwrap();
+    let listing = dump(&program);
+    assert!(listing.starts_with("0: nop +0\n1: acc +1\n2: jmp +4\n"));
+    assert_eq!(parse_program(&listing), Ok(program));
+
+    assert_eq!(
+        parse_program("0: nop +0\n2: acc +1\n"),
+        Err(vec![ParseError::WrongAddress {
+            line: 2,
+            expected: 1,
+            found: 2
+        }])
+    );
+}
+
+#[test]
+fn test_trace_example() {
+    let program = parse_program(include_str!("sample.txt")).unwrap();
+    let mut steps = Vec::new();
+    let outcome = Machine::new(program).run_traced(usize::MAX, |state, ins| {
+        steps.push((state.pc, ins.to_string(), state.acc))
+    });
+    // the puzzle's walkthrough: the 7 instructions that get executed, then the `acc +1` at address 1 that would
+    // run a second time
+    assert_eq!(
+        steps,
+        vec![
+            (0, "nop +0".to_string(), 0),
+            (1, "acc +1".to_string(), 0),
+            (2, "jmp +4".to_string(), 1),
+            (6, "acc +1".to_string(), 1),
+            (7, "jmp -4".to_string(), 2),
+            (3, "acc +3".to_string(), 2),
+            (4, "jmp -3".to_string(), 5),
+            (1, "acc +1".to_string(), 5),
+        ]
+    );
+    assert_eq!(outcome, Outcome::InfiniteLoop { pc: 1, acc: 5 });
+}
+
+#[test]
+fn test_step_limit() {
+    let program = parse_program(include_str!("sample.txt")).unwrap();
+    let mut count = 0;
+    let outcome = Machine::new(program.clone()).run_traced(3, |_, _| count += 1);
+    assert_eq!(count, 3);
+    assert_eq!(outcome, Outcome::StepLimit { pc: 6, acc: 1 });
+
+    // the limit only kicks in if the program would otherwise keep going
+    assert_eq!(
+        Machine::new(program).run_traced(7, |_, _| {}),
+        Outcome::InfiniteLoop { pc: 1, acc: 5 }
+    );
+}
+
+#[test]
+fn test_jump_out_of_bounds() {
+    // a jump to before the first instruction
+    let program = parse_program("nop +0\nacc +1\njmp -5\n").unwrap();
+    let state = State { pc: 2, acc: 1 };
+    assert_eq!(
+        state.next(&program),
+        Err(Fault::JumpOutOfBounds { from: 2, target: -3 })
+    );
+    assert_eq!(
+        Machine::new(program).run(),
+        Outcome::Fault(Fault::JumpOutOfBounds { from: 2, target: -3 })
+    );
+
+    // a jump two past the end, rather than exactly at the end
+    let program = parse_program("nop +0\njmp +3\n").unwrap();
+    assert_eq!(
+        Machine::new(program.clone()).run(),
+        Outcome::Fault(Fault::JumpOutOfBounds { from: 1, target: 4 })
+    );
+    assert_eq!(State { pc: 2, acc: 0 }.next_option(&program), Ok(None));
+    assert_eq!(
+        State { pc: 3, acc: 0 }.next_option(&program),
+        Err(Fault::PcOverrun { pc: 3 })
+    );
+
+    // flipping the first instruction faults, flipping the second one terminates
+    let program = parse_program("nop -3\njmp -1\nacc +1\n").unwrap();
+    assert_eq!(fix_program(&program), Some((1, 1)));
+    assert_eq!(fix_program_fast(&program), Some((1, 1)));
+}
+
+#[test]
+fn test_fix_program_fast_random() {
+    use rand_xoshiro::rand_core::{RngCore, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    // a seeded generator, so the programs are the same on every run
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(0x2545_f491_4f6c_dd1d);
+    let mut random = move |n: u64| rng.next_u64() % n;
+
+    let mut fixed = 0;
+    for _ in 0..2000 {
+        let len = 1 + random(12) as usize;
+        let program: Program = (0..len)
+            .map(|_| Instruction {
+                kind: [InstructionKind::Nop, InstructionKind::Acc, InstructionKind::Jmp][random(3) as usize],
+                operand: random(2 * len as u64 + 3) as isize - len as isize - 1,
             })
-        })
-        .collect();
+            .collect();
+        if let Outcome::Terminated { .. } = Machine::new(program.clone()).run() {
+            continue;
+        }
 
-    loop {
-        for v in &mut variants {
-            v.next();
+        let fast = fix_program_fast(&program);
+        let slow = fix_program(&program);
+        assert_eq!(fast.is_some(), slow.is_some(), "{}", dump(&program));
+        // several flips might work, and the two fixers don't try them in the same order, so check that the fast
+        // one's fix is right, rather than that it's the same as the brute-force one
+        if let Some((index, acc)) = fast {
+            let mut variant = program.clone();
+            flip_kind(&mut variant[index].kind);
+            assert_eq!(
+                Machine::new(variant).run(),
+                Outcome::Terminated { acc },
+                "{}",
+                dump(&program)
+            );
+            fixed += 1;
         }
     }
+    assert!(fixed > 100, "only {} random programs could be fixed", fixed);
+}
+
+#[test]
+fn test_iterator() {
+    let program = parse_program(include_str!("sample.txt")).unwrap();
+    let states: Vec<_> = Machine::new(program.clone()).map(|s| (s.pc, s.acc)).collect();
+    // up to and including the state where `acc +1` at address 1 is about to run a second time
+    assert_eq!(
+        states,
+        vec![(0, 0), (1, 0), (2, 1), (6, 1), (7, 2), (3, 2), (4, 5), (1, 5)]
+    );
+
+    let mut variant = program;
+    flip_kind(&mut variant[7].kind);
+    let len = variant.len();
+    let last = Machine::new(variant).last().unwrap();
+    assert_eq!(last, State { pc: len, acc: 8 });
+
+    // the state whose jump faults is the last one
+    let program = parse_program("nop +0\njmp -2\n").unwrap();
+    assert_eq!(Machine::new(program).map(|s| s.pc).collect::<Vec<_>>(), vec![0, 1]);
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let program = parse_program(include_str!("sample.txt")).unwrap();
+    let mut machine = Machine::new(program);
+    assert_eq!(machine.by_ref().nth(2), Some(State { pc: 2, acc: 1 }));
+    let snapshot = machine.snapshot();
+    assert_eq!(snapshot, State { pc: 6, acc: 1 });
+    let rest: Vec<_> = machine.by_ref().map(|s| (s.pc, s.acc)).collect();
+    assert_eq!(rest, vec![(6, 1), (7, 2), (3, 2), (4, 5), (1, 5)]);
+    assert_eq!(machine.next(), None);
+
+    // after restoring, nothing counts as executed yet, so this time the loop is only noticed back at address 6
+    machine.restore(snapshot);
+    let again: Vec<_> = machine.by_ref().map(|s| (s.pc, s.acc)).collect();
+    assert_eq!(again, vec![(6, 1), (7, 2), (3, 2), (4, 5), (1, 5), (2, 6), (6, 6)]);
+    machine.restore(State::default());
+    assert_eq!(machine.run(), Outcome::InfiniteLoop { pc: 1, acc: 5 });
 }
@@ -0,0 +1,341 @@
+// in `day09/src/lib.rs`
+
+use std::collections::HashMap;
+
+// first_invalid() returns the position and value of the first number (after the preamble) which isn't the sum of
+// two of the `preamble` numbers before it, trying every pair in each window.
+// find_first_invalid() does the same thing faster, this is kept to check it against.
+#[cfg(test)]
+fn first_invalid(numbers: &[u64], preamble: usize) -> Option<(usize, u64)> {
+    use itertools::Itertools;
+
+    numbers.windows(preamble + 1).enumerate().find_map(|(i, s)| {
+        if s[..preamble]
+            .iter()
+            .tuple_combinations()
+            .any(|(a, b)| a.checked_add(*b) == Some(s[preamble]))
+        {
+            None
+        } else {
+            Some((i + preamble, s[preamble]))
+        }
+    })
+}
+
+// Window keeps count of the values in the window as it slides along (the count matters, a number equal to twice
+// `x` is only valid if `x` appears twice), so checking a number only needs a single pass over the window, looking
+// up `target - x` for each `x` in it. Only ever subtracting from the target (rather than adding pairs) means
+// huge values can't overflow into a false match.
+struct Window {
+    counts: HashMap<u64, usize>,
+}
+
+impl Window {
+    fn new(values: &[u64]) -> Self {
+        let mut counts = HashMap::new();
+        for &x in values {
+            *counts.entry(x).or_default() += 1;
+        }
+        Self { counts }
+    }
+
+    // is_valid() tells whether `target` is the sum of two entries of the window, whose values are `values`
+    fn is_valid(&self, values: &[u64], target: u64) -> bool {
+        values.iter().any(|&x| {
+            x <= target
+                && match self.counts.get(&(target - x)) {
+                    Some(&count) => target - x != x || count >= 2,
+                    None => false,
+                }
+        })
+    }
+
+    // slide() moves the window along by one number, dropping `outgoing` and taking in `incoming`
+    fn slide(&mut self, outgoing: u64, incoming: u64) {
+        if let Some(count) = self.counts.get_mut(&outgoing) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&outgoing);
+            }
+        }
+        *self.counts.entry(incoming).or_default() += 1;
+    }
+}
+
+/// Every number (after the preamble) which isn't the sum of two of the `preamble` numbers before it,
+/// with its position
+pub fn invalid_numbers(nums: &[u64], preamble: usize) -> impl Iterator<Item = (usize, u64)> + '_ {
+    let mut window = Window::new(&nums[..preamble.min(nums.len())]);
+    nums.iter().enumerate().skip(preamble).filter_map(move |(i, &target)| {
+        let valid = window.is_valid(&nums[i - preamble..i], target);
+        window.slide(nums[i - preamble], target);
+        (!valid).then_some((i, target))
+    })
+}
+
+pub fn find_first_invalid(nums: &[u64], preamble: usize) -> Option<(usize, u64)> {
+    invalid_numbers(nums, preamble).next()
+}
+
+// find_all_invalid() carries on after the first invalid number, which stays in the window like any other
+pub fn find_all_invalid(nums: &[u64], preamble: usize) -> Vec<(usize, u64)> {
+    invalid_numbers(nums, preamble).collect()
+}
+
+// contiguous_range() finds a contiguous set of at least two numbers which sum to `target`, by summing every window
+// of every length. find_weakness() is the fast version, this is kept to check it against.
+#[cfg(test)]
+fn contiguous_range(numbers: &[u64], target: u64) -> Option<&[u64]> {
+    let (n, i, _) = (2..numbers.len())
+        .flat_map(|n| {
+            numbers
+                .windows(n)
+                .enumerate()
+                .map(move |(i, s)| (n, i, s.iter().map(|&x| x as u128).sum::<u128>()))
+        })
+        .find(|&(_, _, sum)| sum == target as u128)?;
+    Some(&numbers[i..][..n])
+}
+
+// find_weakness() finds the bounds `start..end` of a contiguous set of at least two numbers which sum to `target`.
+// As the numbers are all positive, it's enough to grow the set at the end while its sum is too small, and shrink
+// it from the start while the sum is too big. The sum is kept in a u128, which can't overflow however close to
+// u64::MAX the numbers are (a u64 sum could wrap around to the target).
+pub fn find_weakness(nums: &[u64], target: u64) -> Option<(usize, usize)> {
+    let (mut start, mut sum, target) = (0, 0u128, target as u128);
+    for end in 0..nums.len() {
+        sum += nums[end] as u128;
+        while sum > target && start <= end {
+            sum -= nums[start] as u128;
+            start += 1;
+        }
+        if sum == target && end > start {
+            return Some((start, end + 1));
+        }
+    }
+    None
+}
+
+// weakness() adds the smallest and largest number of the set found by find_weakness()
+pub fn weakness(nums: &[u64], target: u64) -> Option<u64> {
+    Weakness::find(nums, target).map(|weakness| weakness.sum_of_min_max)
+}
+
+/// A line that isn't a number. Line numbers are 1-based.
+#[derive(thiserror::Error, Debug, PartialEq)]
+#[error("line {line}: expected a number, got {text:?}")]
+pub struct ParseError {
+    pub line: usize,
+    pub text: String,
+}
+
+// parse_numbers() reads one number per line, ignoring surrounding whitespace and blank lines
+pub fn parse_numbers(input: &str) -> Result<Vec<u64>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(index, l)| {
+            l.trim().parse::<u64>().map_err(|_| ParseError {
+                line: index + 1,
+                text: l.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The first number that isn't the sum of two of the numbers before it, and its position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Invalid {
+    pub index: usize,
+    pub value: u64,
+}
+
+/// The contiguous set of numbers `start..end` which sums to the invalid number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weakness {
+    pub start: usize,
+    pub end: usize,
+    pub min: u64,
+    pub max: u64,
+    pub sum_of_min_max: u64,
+}
+
+impl Weakness {
+    /// Finds the contiguous set of at least two of `numbers` which sums to `target`
+    pub fn find(numbers: &[u64], target: u64) -> Option<Weakness> {
+        let (start, end) = find_weakness(numbers, target)?;
+        let set = &numbers[start..end];
+        let (min, max) = (*set.iter().min()?, *set.iter().max()?);
+        Some(Weakness {
+            start,
+            end,
+            min,
+            max,
+            // can't overflow: min and max are two of the numbers adding up to `target`
+            sum_of_min_max: min + max,
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum Error {
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error("every number is the sum of two of the ones before it")]
+    NoInvalidNumber,
+    #[error("no contiguous set of numbers sums to {target}")]
+    NoWeakness { target: u64 },
+}
+
+pub fn part1(input: &str, preamble: usize) -> Result<Invalid, Error> {
+    let (index, value) = find_first_invalid(&parse_numbers(input)?, preamble).ok_or(Error::NoInvalidNumber)?;
+    Ok(Invalid { index, value })
+}
+
+pub fn part2(input: &str, preamble: usize) -> Result<Weakness, Error> {
+    let numbers = parse_numbers(input)?;
+    let (_, target) = find_first_invalid(&numbers, preamble).ok_or(Error::NoInvalidNumber)?;
+    Weakness::find(&numbers, target).ok_or(Error::NoWeakness { target })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = include_str!("sample.txt");
+
+    #[test]
+    fn test_example() {
+        let numbers = parse_numbers(SAMPLE).unwrap();
+        assert_eq!(first_invalid(&numbers, 5), Some((14, 127)));
+        assert_eq!(find_first_invalid(&numbers, 5), Some((14, 127)));
+        assert_eq!(contiguous_range(&numbers, 127), Some(&[15, 25, 47, 40][..]));
+        assert_eq!(find_weakness(&numbers, 127), Some((2, 6)));
+        assert_eq!(weakness(&numbers, 127), Some(62));
+    }
+
+    #[test]
+    fn test_find_weakness_none() {
+        assert_eq!(find_weakness(&[1, 2, 3], 100), None);
+        // a single number doesn't count
+        assert_eq!(find_weakness(&[5, 1, 7], 7), None);
+        assert_eq!(weakness(&[5, 1, 7], 7), None);
+        assert_eq!(find_weakness(&[], 0), None);
+        // but it's fine for the set to start right after it
+        assert_eq!(find_weakness(&[7, 3, 4], 7), Some((1, 3)));
+    }
+
+    #[test]
+    fn test_find_first_invalid_doubles() {
+        // 10 is only the sum of two entries if 5 is in the window twice
+        assert_eq!(find_first_invalid(&[5, 1, 2, 10], 3), Some((3, 10)));
+        assert_eq!(find_first_invalid(&[5, 1, 5, 10], 3), None);
+        // ... and once the first 5 slides out, it's back to once
+        assert_eq!(find_first_invalid(&[5, 5, 1, 10, 10], 2), Some((2, 1)));
+        assert_eq!(find_first_invalid(&[5, 5, 10, 10], 2), Some((3, 10)));
+    }
+
+    #[test]
+    fn test_find_first_invalid_random() {
+        use rand_xoshiro::rand_core::{RngCore, SeedableRng};
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // a seeded generator, so the data is the same on every run
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0x9e37_79b9_7f4a_7c15);
+        let mut random = move |n: u64| rng.next_u64() % n;
+
+        for _ in 0..2000 {
+            let preamble = random(6) as usize;
+            let numbers: Vec<u64> = (0..random(30)).map(|_| random(40)).collect();
+            assert_eq!(
+                find_first_invalid(&numbers, preamble),
+                first_invalid(&numbers, preamble),
+                "{:?} with a preamble of {}",
+                numbers,
+                preamble
+            );
+        }
+    }
+
+    #[test]
+    fn test_parts() {
+        assert_eq!(part1(SAMPLE, 5), Ok(Invalid { index: 14, value: 127 }));
+        assert_eq!(
+            part2(SAMPLE, 5),
+            Ok(Weakness {
+                start: 2,
+                end: 6,
+                min: 15,
+                max: 47,
+                sum_of_min_max: 62
+            })
+        );
+    }
+
+    #[test]
+    fn test_no_invalid_number() {
+        // each number is the sum of the two before it
+        let input = "1\n2\n3\n5\n8\n13\n";
+        assert_eq!(part1(input, 2), Err(Error::NoInvalidNumber));
+        assert_eq!(part2(input, 2), Err(Error::NoInvalidNumber));
+        // 12 is invalid, but nothing adds up to it
+        assert_eq!(part2("1\n2\n3\n12\n", 3), Err(Error::NoWeakness { target: 12 }));
+    }
+
+    #[test]
+    fn test_parse_numbers() {
+        assert_eq!(parse_numbers("35\n20\n 15 \n\n"), Ok(vec![35, 20, 15]));
+        assert_eq!(
+            parse_numbers("35\n\n12 34\n20\n"),
+            Err(ParseError {
+                line: 3,
+                text: "12 34".to_string()
+            })
+        );
+        assert_eq!(
+            part1("35\n-20\n", 1),
+            Err(Error::Parse(ParseError {
+                line: 2,
+                text: "-20".to_string()
+            }))
+        );
+    }
+
+    #[test]
+    fn test_find_all_invalid() {
+        // every number is the sum of the two before it, except for the planted 100 and 11
+        let numbers = [1, 2, 3, 5, 8, 100, 108, 208, 11, 219];
+        assert_eq!(find_all_invalid(&numbers, 2), vec![(5, 100), (8, 11)]);
+        assert_eq!(find_first_invalid(&numbers, 2), Some((5, 100)));
+
+        assert_eq!(Weakness::find(&numbers, 100), None);
+        let weakness = Weakness::find(&numbers, 11).unwrap();
+        assert_eq!((weakness.start, weakness.end, weakness.sum_of_min_max), (0, 4, 6));
+
+        assert!(find_all_invalid(&numbers[..5], 2).is_empty());
+        assert!(find_all_invalid(&numbers, 20).is_empty());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let big = u64::MAX - 1;
+        // big + 5 wraps around to 3 in u64 arithmetic, but 3 isn't the sum of the two before it
+        let numbers = [big, 5, 3, 8];
+        assert_eq!(find_first_invalid(&numbers, 2), Some((2, 3)));
+        assert_eq!(first_invalid(&numbers, 2), Some((2, 3)));
+        assert_eq!(find_all_invalid(&numbers, 2), vec![(2, 3)]);
+        // ... nor is it the sum of a contiguous range
+        assert_eq!(find_weakness(&numbers, 3), None);
+        assert_eq!(contiguous_range(&numbers, 3), None);
+
+        // sums right up to u64::MAX still work
+        let numbers = [big, 1, u64::MAX];
+        assert_eq!(find_first_invalid(&numbers, 2), None);
+        assert_eq!(
+            Weakness::find(&numbers, u64::MAX).map(|w| (w.start, w.end)),
+            Some((0, 2))
+        );
+        assert_eq!(weakness(&[1, big], u64::MAX), Some(u64::MAX));
+    }
+}
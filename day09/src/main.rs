@@ -1,51 +1,63 @@
-use itertools::Itertools;
+use anyhow::Context;
+use day09::Weakness;
+use std::io::Read;
 
-fn main() {
-    let numbers = include_str!("input.txt")
-        .lines()
-        .map(|x| x.parse::<usize>().unwrap())
-        .collect::<Vec<_>>();
-
-    let n = 25;
-    let answer = numbers.windows(n + 1).find_map(|s| {
-        if (&s[..n])
-            .iter()
-            .tuple_combinations()
-            .any(|(a, b)| a + b == s[n])
-        {
-            None
-        } else {
-            Some(s[n])
+fn main() -> anyhow::Result<()> {
+    let (mut path, mut preamble, mut all) = (None, 25, false);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // the puzzle example uses a preamble of 5
+            "--preamble" => {
+                let n = args.next().context("--preamble must be followed by a length")?;
+                preamble = n.parse().with_context(|| format!("invalid preamble length {:?}", n))?;
+            }
+            // list every invalid number, each with its weakness if there is one
+            "--all" => all = true,
+            _ => path = Some(arg),
         }
-    });
-    println!("Part 1:");
-    println!("  answer = {:?}", answer);
+    }
 
-    let answer = answer.unwrap();
-
-    /*
-    // simply try to find a contiguous set of numbers whose sum is the same as the answer we found in part1
-    // we don't know afterwards where or how large the set was
-    let _answer = (2..numbers.len())
-        .into_iter().flat_map(|n| numbers.windows(n).map(|s| s.iter().sum::<usize>()))
-        .find(|&n| n == answer);
-    println!("  answer2 = {:?}", _answer);
-    */
+    // `day09 <path>` reads the numbers from a file and `day09 -` from stdin,
+    // without arguments we use the embedded input.txt
+    let input = match path.as_deref() {
+        None => include_str!("input.txt").to_string(),
+        Some("-") => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            input
+        }
+        Some(path) => std::fs::read_to_string(path).with_context(|| format!("couldn't open {}", path))?,
+    };
 
-    let answer2 = (2..numbers.len())
-        .into_iter().flat_map(|n| {
-            numbers
-                .windows(n)
-                .enumerate()
-                .map(move |(i, s)| (n, i, s.iter().sum::<usize>()))
-        })
-        .find(|&(_, _, sum)| sum == answer);
+    if all {
+        let numbers = day09::parse_numbers(&input)?;
+        for (index, value) in day09::find_all_invalid(&numbers, preamble) {
+            match Weakness::find(&numbers, value) {
+                Some(w) => println!(
+                    "number {} = {}: numbers {}..{} sum to it, min() + max() = {}",
+                    index, value, w.start, w.end, w.sum_of_min_max
+                ),
+                None => println!("number {} = {}: no contiguous range sums to it", index, value),
+            }
+        }
+        return Ok(());
+    }
 
-    let (n, i, _) = answer2.unwrap();
-    let set = &numbers[i..][..n];
+    let invalid = day09::part1(&input, preamble)?;
+    println!("Part 1:");
+    println!("  answer = {} (number {})", invalid.value, invalid.index);
 
+    let weakness = day09::part2(&input, preamble)?;
     println!("Part 2:");
-    println!("  sum({:?}) = {}", set, answer);
-    let answer3 = set.iter().max().unwrap() + set.iter().min().unwrap();
-    println!("  sum of min() and max() for this contiguous range = {}", answer3);
+    println!(
+        "  numbers {}..{} sum to {}, the smallest is {} and the largest {}",
+        weakness.start, weakness.end, invalid.value, weakness.min, weakness.max
+    );
+    println!(
+        "  sum of min() and max() for this contiguous range = {}",
+        weakness.sum_of_min_max
+    );
+
+    Ok(())
 }
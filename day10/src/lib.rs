@@ -0,0 +1,294 @@
+// in `day10/src/lib.rs`
+
+/// How many times each gap size appears between consecutive adapters in a chain
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct GapCounts {
+    pub ones: usize,
+    pub twos: usize,
+    pub threes: usize,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ChainError {
+    #[error("the gap between adapters {from} and {to} is more than {max_gap} jolts")]
+    GapTooLarge { from: usize, to: usize, max_gap: usize },
+    #[error("line {line}: expected a joltage, got {text:?}")]
+    NotANumber { line: usize, text: String },
+    #[error("there's more than one adapter rated {0} jolts")]
+    Duplicate(usize),
+    #[error("adapters must tolerate a gap of at least 1 jolt")]
+    ZeroMaxGap,
+}
+
+/// The biggest difference in joltage an adapter tolerates, as per the puzzle
+pub const MAX_GAP: usize = 3;
+
+// parse_adapters() sorts the adapters' joltages, adding the charging outlet (0) and the device (max + 3)
+pub fn parse_adapters(input: &str) -> Result<Vec<usize>, ChainError> {
+    parse_adapters_with(input, MAX_GAP)
+}
+
+// parse_adapters_with() is parse_adapters() for adapters tolerating a difference of `max_gap`, which the device's
+// built-in adapter is rated above the highest one.
+// Two adapters with the same rating are rejected: an adapter can't take the output of one rated the same, so
+// they'd be alternatives rather than links in the chain, and everything else here expects a joltage per adapter.
+// For the same reason `max_gap` can't be 0, which would rate the device the same as the highest adapter.
+pub fn parse_adapters_with(input: &str, max_gap: usize) -> Result<Vec<usize>, ChainError> {
+    if max_gap == 0 {
+        return Err(ChainError::ZeroMaxGap);
+    }
+    // we have this list of numbers (blank lines aside)...
+    let mut numbers = vec![0];
+    for (index, l) in input.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        numbers.push(l.trim().parse().map_err(|_| ChainError::NotANumber {
+            line: index + 1,
+            text: l.to_string(),
+        })?);
+    }
+    // clippy told me to use `sort_unstable`
+    numbers.sort_unstable();
+
+    // to which we need to add 0 and whatever the maximum was plus three
+    if let Some(&max) = numbers.iter().max() {
+        // numbers is still sorted after this
+        numbers.push(max + max_gap);
+    }
+    if let Some(s) = numbers.windows(2).find(|s| s[0] == s[1]) {
+        return Err(ChainError::Duplicate(s[0]));
+    }
+    Ok(numbers)
+}
+
+// if we take the adapters in order, we'll have gaps of 1 up to `max_gap`, anything bigger breaks the chain.
+// count_gaps_with() counts how many there are of each, indexed by gap size (so the count at index 0 is always 0)
+// recent rust versions allow use of the method array_windows [usize; 2]
+pub fn count_gaps_with(sorted: &[usize], max_gap: usize) -> Result<Vec<usize>, ChainError> {
+    let mut counts = vec![0; max_gap + 1];
+    for s in sorted.windows(2) {
+        if let &[x, y] = s {
+            match y - x {
+                0 => return Err(ChainError::Duplicate(x)),
+                gap if gap <= max_gap => counts[gap] += 1,
+                _ => {
+                    return Err(ChainError::GapTooLarge {
+                        from: x,
+                        to: y,
+                        max_gap,
+                    })
+                }
+            }
+        }
+    }
+    Ok(counts)
+}
+
+// count_gaps() is count_gaps_with() for the puzzle's own maximum gap of 3
+pub fn count_gaps(sorted: &[usize]) -> Result<GapCounts, ChainError> {
+    let counts = count_gaps_with(sorted, MAX_GAP)?;
+    Ok(GapCounts {
+        ones: counts[1],
+        twos: counts[2],
+        threes: counts[3],
+    })
+}
+
+// part2 wants all the possible ways in which we can connect our adapters
+// given 1, 2, 3, 5, 6 ... [1 2 3 5 6], [1 2 3 6], [1 2 5 6], [1 3 5 6], or [1 3 6] = 5 ways
+// ways to 6
+// node_6 = 1
+// node_5 = node_6 = 1
+// node3 = node_5 + node_6 = 1 + 1 = 2
+// node 2 = node_3 + node_5 = 2 + 1 = 3
+// node_1 = node_2 + node_3 = 3 + 2 = 5
+// rules stipulate an initial node of 0 and a final node of max+3
+// paths_from() counts, for each adapter in the chain, the ways to get from it to the device, indexed by position.
+// As the joltages are all different, the adapters within `max_gap` jolts are at most `max_gap` positions further on
+pub fn paths_from(sorted: &[usize], max_gap: usize) -> Vec<u128> {
+    let n = sorted.len();
+    let mut paths = vec![0; n];
+    if let Some(last) = paths.last_mut() {
+        *last = 1;
+    }
+    for i in (0..n.saturating_sub(1)).rev() {
+        paths[i] = ((i + 1)..=std::cmp::min(i + max_gap, n - 1))
+            .filter(|&j| (1..=max_gap).contains(&(sorted[j] - sorted[i])))
+            .map(|j| paths[j])
+            .sum();
+    }
+    paths
+}
+
+// count_arrangements_with() is the number of ways to get from the charging outlet to the device. That's already
+// over 2^40 for the puzzle input, and allowing bigger gaps takes it past u64::MAX
+pub fn count_arrangements_with(sorted: &[usize], max_gap: usize) -> u128 {
+    paths_from(sorted, max_gap).first().copied().unwrap_or(0)
+}
+
+pub fn count_arrangements(sorted: &[usize]) -> u128 {
+    count_arrangements_with(sorted, MAX_GAP)
+}
+
+// arrangements() lists the ways to get from the first to the last of the `sorted` joltages (for parse_adapters()
+// output, from the charging outlet to the device), using a depth-first search that only goes as far as the next
+// arrangement each time, and stepping up by at most `max_gap` jolts. There are far too many to list for the puzzle
+// input, so a `limit` must be given.
+pub fn arrangements(sorted: &[usize], max_gap: usize, limit: usize) -> impl Iterator<Item = Vec<usize>> + '_ {
+    // each entry is an adapter's position in `sorted`, and the position of the next adapter to try after it
+    let mut stack: Vec<(usize, usize)> = if sorted.is_empty() { vec![] } else { vec![(0, 1)] };
+    std::iter::from_fn(move || loop {
+        let (i, j) = stack.last_mut()?;
+        if *i == sorted.len() - 1 {
+            let found = stack.iter().map(|&(i, _)| sorted[i]).collect();
+            stack.pop();
+            return Some(found);
+        }
+        if *j < sorted.len() && sorted[*j] - sorted[*i] <= max_gap {
+            let next = *j;
+            *j += 1;
+            stack.push((next, next + 1));
+        } else {
+            stack.pop();
+        }
+    })
+    .take(limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_gaps_examples() {
+        let gaps = count_gaps(&parse_adapters(include_str!("sample1.txt")).unwrap()).unwrap();
+        assert_eq!((gaps.ones, gaps.twos, gaps.threes), (7, 0, 5));
+        let gaps = count_gaps(&parse_adapters(include_str!("sample2.txt")).unwrap()).unwrap();
+        assert_eq!((gaps.ones, gaps.twos, gaps.threes), (22, 0, 10));
+    }
+
+    #[test]
+    fn test_count_gaps_two() {
+        // a gap of 2 is fine, it just isn't one of the gaps part 1 multiplies
+        assert_eq!(
+            count_gaps(&parse_adapters("1\n3\n4\n").unwrap()),
+            Ok(GapCounts {
+                ones: 2,
+                twos: 1,
+                threes: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_count_gaps_broken() {
+        assert_eq!(
+            count_gaps(&parse_adapters("1\n2\n6\n7\n").unwrap()),
+            Err(ChainError::GapTooLarge {
+                from: 2,
+                to: 6,
+                max_gap: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_count_arrangements_examples() {
+        // sample0.txt contains 1, 2, 3, 5, 6.
+        // 5 ways from adapter 1 as worked out for paths_from(), but the outlet can also plug straight into 2 or 3
+        let numbers = parse_adapters(include_str!("sample0.txt")).unwrap();
+        assert_eq!(paths_from(&numbers, 3), vec![10, 5, 3, 2, 1, 1, 1]);
+        assert_eq!(count_arrangements(&numbers), 10);
+        assert_eq!(
+            count_arrangements(&parse_adapters(include_str!("sample1.txt")).unwrap()),
+            8
+        );
+        assert_eq!(
+            count_arrangements(&parse_adapters(include_str!("sample2.txt")).unwrap()),
+            19208
+        );
+    }
+
+    #[test]
+    fn test_max_gap() {
+        // with a maximum gap of 1, only a run of consecutive joltages makes a chain, and there's one way through it
+        let numbers = parse_adapters_with("3\n1\n2\n", 1).unwrap();
+        assert_eq!(numbers, vec![0, 1, 2, 3, 4]);
+        assert_eq!(count_gaps_with(&numbers, 1), Ok(vec![0, 4]));
+        assert_eq!(count_arrangements_with(&numbers, 1), 1);
+
+        let numbers = parse_adapters_with(include_str!("sample1.txt"), 1).unwrap();
+        assert_eq!(
+            count_gaps_with(&numbers, 1),
+            Err(ChainError::GapTooLarge {
+                from: 1,
+                to: 4,
+                max_gap: 1
+            })
+        );
+        assert_eq!(count_arrangements_with(&numbers, 1), 0);
+
+        let numbers = parse_adapters_with(include_str!("sample1.txt"), 4).unwrap();
+        assert_eq!(count_gaps_with(&numbers, 4), Ok(vec![0, 7, 0, 4, 1]));
+        assert_eq!(count_arrangements_with(&numbers, 4), 170);
+
+        // rather than a device rated the same as the last adapter, which would look like a duplicate
+        assert_eq!(parse_adapters_with("3\n1\n2\n", 0), Err(ChainError::ZeroMaxGap));
+    }
+
+    #[test]
+    fn test_duplicates() {
+        assert_eq!(parse_adapters("1\n2\n2\n3\n"), Err(ChainError::Duplicate(2)));
+        // the outlet and the device count too
+        assert_eq!(parse_adapters("0\n1\n"), Err(ChainError::Duplicate(0)));
+        assert_eq!(count_gaps_with(&[0, 1, 1, 2, 5], 3), Err(ChainError::Duplicate(1)));
+    }
+
+    #[test]
+    fn test_arrangements() {
+        // the ways from 1 to 6 worked out for paths_from()
+        let found: Vec<_> = arrangements(&[1, 2, 3, 5, 6], 3, 100).collect();
+        assert_eq!(
+            found,
+            vec![
+                vec![1, 2, 3, 5, 6],
+                vec![1, 2, 3, 6],
+                vec![1, 2, 5, 6],
+                vec![1, 3, 5, 6],
+                vec![1, 3, 6]
+            ]
+        );
+        assert_eq!(arrangements(&[1, 2, 3, 5, 6], 3, 2).count(), 2);
+
+        let numbers = parse_adapters(include_str!("sample1.txt")).unwrap();
+        let found: Vec<_> = arrangements(&numbers, 3, 100).collect();
+        assert_eq!(found.len() as u128, count_arrangements(&numbers));
+        assert_eq!(found[0], numbers);
+        assert!(found.iter().all(|a| a[0] == 0 && a.last() == Some(&22)));
+
+        // ... every one of which has gaps of 3 in it
+        assert_eq!(arrangements(&numbers, 2, 100).count(), 0);
+
+        // a broken chain has no arrangements
+        assert_eq!(arrangements(&[0, 1, 5], 3, 100).count(), 0);
+        assert_eq!(arrangements(&[], 3, 100).count(), 0);
+    }
+
+    #[test]
+    fn test_part1_examples() {
+        let gaps = count_gaps(&parse_adapters(include_str!("sample1.txt")).unwrap()).unwrap();
+        assert_eq!(gaps.ones * gaps.threes, 35);
+        let gaps = count_gaps(&parse_adapters(include_str!("sample2.txt")).unwrap()).unwrap();
+        assert_eq!(gaps.ones * gaps.threes, 220);
+    }
+
+    #[test]
+    fn test_parse_adapters() {
+        assert_eq!(parse_adapters("3\n1\n\n"), Ok(vec![0, 1, 3, 6]));
+        assert_eq!(
+            parse_adapters("3\n1\nx\n"),
+            Err(ChainError::NotANumber {
+                line: 3,
+                text: "x".to_string()
+            })
+        );
+    }
+}
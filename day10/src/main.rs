@@ -1,105 +1,53 @@
-use std::collections::HashMap;
-
-#[derive(Default, Clone, Copy, Debug)]
-struct Results {
-    ones: usize,
-    threes: usize,
-}
-
-fn main() {
-
-    // we have this list of numbers...
-    let mut numbers: Vec<_> = std::iter::once(0)
-        .chain(
-            include_str!("input.txt")
-                .lines()
-                .map(|x| x.parse::<usize>().unwrap()),
-        )
-        .collect();
-    // clippy told me to use `sort_unstable`
-    numbers.sort_unstable();
-
-    // to which we need to add 0 and whatever the maximum was plus three
-    if let Some(&max) = numbers.iter().max() {
-        // numbers is still sorted after this
-        numbers.push(max + 3);
-    }
-
-    // from there on, if we take them in order, we'll have gaps of 1 and gaps of 3
-    // we need to multiply the amount of 1-gaps with the amount of 3-gaps
-    // recent rust versions allow use of the method array_windows [usize; 2]
-    let results = numbers.windows(2).fold(Results::default(), |acc, s| {
-        if let [x, y] = s {
-            match y - x {
-                1 => Results {
-                    ones: acc.ones + 1,
-                    ..acc
-                },
-                3 => Results {
-                    threes: acc.threes + 1,
-                    ..acc
-                },
-                gap => panic!("invalid input (found {} gap)", gap),
+use anyhow::Context;
+use day10::{arrangements, count_arrangements_with, count_gaps_with, parse_adapters_with, paths_from, MAX_GAP};
+
+fn main() -> anyhow::Result<()> {
+    let (mut verbose, mut max_gap, mut list) = (false, MAX_GAP, None);
+    let mut args = std::env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // print how many paths there are from every adapter
+            "--verbose" => verbose = true,
+            // what if adapters tolerated a difference of, say, 4 jolts?
+            "--max-gap" => {
+                let n = args.next().context("--max-gap must be followed by a number of jolts")?;
+                max_gap = n.parse().with_context(|| format!("invalid maximum gap {:?}", n))?;
+            }
+            // print the first few arrangements themselves, 10 unless a limit follows
+            "--list" => {
+                let limit = args.next_if(|n| n.parse::<usize>().is_ok());
+                list = Some(limit.map_or(10, |n| n.parse().unwrap()));
             }
-        } else {
-            unreachable!()
+            _ => anyhow::bail!("usage: day10 [--verbose] [--max-gap N] [--list [LIMIT]]"),
         }
-    });
-    dbg!(results, results.ones * results.threes);
-
-    // part2 wants all the possible ways in which we can connect our adapters
-    // given 1, 2, 3, 5, 6 ... [1 2 3 5 6], [1 2 3 6], [1 2 5 6], [1 3 5 6], or [1 3 6] = 5 ways
-    // ways to 6
-    // node_6 = 1
-    // node_5 = node_6 = 1
-    // node3 = node_5 + node_6 = 1 + 1 = 2
-    // node 2 = node_3 + node_5 = 2 + 1 = 3
-    // node_1 = node_2 + node_3 = 3 + 2 = 5
-    // rules stipulate an initial node of 0 and a final node of max+3
-
-    let mut numbers: Vec<_> = std::iter::once(0)
-        .chain(
-            // sample0.txt file contains 1, 2, 3, 5, 6
-            include_str!("input.txt")
-                .lines()
-                .map(|x| x.parse::<usize>().unwrap()),
-        )
-        .collect();
-    numbers.sort_unstable();
-
-    // numbers is still sorted after this
-    numbers.push(numbers.iter().max().unwrap() + 3);
-
-    let mut num_paths = HashMap::new();
+    }
 
-    let n = numbers.len();
-    num_paths.insert(numbers.last().copied().unwrap(), 1);
-    for i in (0..(numbers.len() - 1)).into_iter().rev() {
-        let i_val = numbers[i];
-        let range = (i + 1)..=std::cmp::min(i + 3, n - 1);
+    let numbers = parse_adapters_with(include_str!("input.txt"), max_gap)?;
 
-        let num_neighbors: usize = range
-            .filter_map(|j| {
-                let j_val = numbers[j];
-                let gap = j_val - i_val;
-                if (1..=3).contains(&gap) {
-                    Some(num_paths.get(&j_val).unwrap())
-                } else {
-                    None
-                }
-            })
-            .sum();
-        num_paths.insert(i_val, num_neighbors);
+    // we need to multiply the amount of 1-gaps with the amount of 3-gaps
+    let gaps = count_gaps_with(&numbers, max_gap)?;
+    println!("Part 1:");
+    for (gap, count) in gaps.iter().enumerate().skip(1) {
+        println!("  {} gaps of {}", count, gap);
     }
+    let count = |gap: usize| gaps.get(gap).copied().unwrap_or(0);
+    println!("  ones * threes = {}", count(1) * count(3));
 
-    for &n in numbers.iter().rev() {
-        let &m = num_paths.get(&n).unwrap();
-        println!(
-            "from {}, there's {} {}",
-            n,
-            m,
-            if m == 1 { "path" } else { "paths" }
-        );
+    if verbose {
+        for (&n, &m) in numbers.iter().zip(&paths_from(&numbers, max_gap)).rev() {
+            println!("from {}, there's {} {}", n, m, if m == 1 { "path" } else { "paths" });
+        }
+    }
+    if let Some(limit) = list {
+        for arrangement in arrangements(&numbers, max_gap, limit) {
+            println!(
+                "{}",
+                arrangement.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(" ")
+            );
+        }
     }
+    println!("Part 2:");
+    println!("  {} arrangements", count_arrangements_with(&numbers, max_gap));
 
+    Ok(())
 }
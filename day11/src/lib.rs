@@ -0,0 +1,722 @@
+// in `day11/src/lib.rs`
+
+use itertools::Itertools;
+use std::fmt;
+use std::iter::Extend;
+use std::ops::{Index, IndexMut};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vec2 {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Vec2 {
+    // from_index() finds the position of a tile in a map `width` tiles wide, from its index in row order
+    fn from_index(index: usize, width: i64) -> Self {
+        let index = index as i64;
+        Self {
+            x: index % width,
+            y: index / width,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Tile {
+    #[default]
+    Floor,
+    EmptySeat,
+    OccupiedSeat,
+}
+
+impl fmt::Debug for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match self {
+            Tile::Floor => '.',
+            Tile::EmptySeat => 'L',
+            Tile::OccupiedSeat => '#',
+        };
+        write!(f, "{}", c)
+    }
+}
+
+/// Which seats a passenger looks at when deciding whether to sit down or get up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sight {
+    /// Only the eight seats right next to theirs
+    Adjacent,
+    /// The first seat they can see in each of the eight directions, over any floor
+    LineOfSight,
+}
+
+/// How seats fill up and empty out from one generation to the next
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rules {
+    /// An occupied seat is given up when at least this many of its neighbours are occupied
+    pub crowd_threshold: usize,
+    pub sight: Sight,
+}
+
+impl Rules {
+    pub const PART1: Rules = Rules {
+        crowd_threshold: 4,
+        sight: Sight::Adjacent,
+    };
+    pub const PART2: Rules = Rules {
+        crowd_threshold: 5,
+        sight: Sight::LineOfSight,
+    };
+}
+
+impl Tile {
+    fn next<I>(self, neighbors: I, rules: Rules) -> Self
+    where
+        I: Iterator<Item = Self>,
+    {
+        match self {
+            Self::Floor => Self::Floor,
+            Self::EmptySeat => match neighbors
+                .filter(|t| matches!(t, Self::OccupiedSeat))
+                .count() {
+                // no one around? we can sit here!
+                0 => Self::OccupiedSeat,
+                // social distancing please
+                _ => Self::EmptySeat,
+            },
+            Self::OccupiedSeat => {
+                match neighbors
+                    .filter(|t| matches!(t, Self::OccupiedSeat))
+                    .count() {
+                    // up to 3 neighbors (4 for part 2): still ok for now
+                    n if n < rules.crowd_threshold => Self::OccupiedSeat,
+                    // that's too many folks!
+                    _ => Self::EmptySeat,
+                }
+            }
+        }
+    }
+}
+
+/// For each tile of a map (by index), the indices of the seats that count as its neighbours.
+/// Floor tiles never change, so they have none.
+#[derive(Debug, Clone, PartialEq)]
+struct Neighbours {
+    indices: Vec<Vec<usize>>,
+}
+
+impl Neighbours {
+    fn of(&self, index: usize) -> &[usize] {
+        &self.indices[index]
+    }
+}
+
+/// A problem with the seat layout. Rows and columns are 1-based.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ParseError {
+    #[error("the seat layout is empty")]
+    Empty,
+    #[error("row {row}: blank line")]
+    EmptyRow { row: usize },
+    #[error("row {row}: expected {expected} tiles like the first row, but found {found}")]
+    RaggedRow { row: usize, expected: usize, found: usize },
+    #[error("row {row}, column {column}: expected '.', 'L' or '#', but got {:?}", char::from(*.byte))]
+    InvalidTile { row: usize, column: usize, byte: u8 },
+}
+
+// Note: Vec2 already derives PartialEq. As for T, it might or it might not.
+// Map<T> will only implement PartialEq if T itself implements PartialEq.
+
+#[derive(PartialEq, Clone)]
+pub struct Map<T>
+where
+    T: Clone,
+{
+    size: Vec2,
+    tiles: Vec<T>,
+}
+
+impl<T> fmt::Debug for Map<T>
+where
+    T: fmt::Debug + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.size.y {
+            for x in 0..self.size.x {
+                write!(f, "{:?}", self[Vec2 { x, y }])?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// Indexing panics for positions outside the map, use get() when that may happen.
+impl<T> Index<Vec2> for Map<T>
+where
+    T: Clone,
+{
+    type Output = T;
+
+    fn index(&self, pos: Vec2) -> &T {
+        match self.index_of(pos) {
+            Some(index) => &self.tiles[index],
+            None => panic!("{:?} is outside of a {:?} map", pos, self.size),
+        }
+    }
+}
+
+impl<T> IndexMut<Vec2> for Map<T>
+where
+    T: Clone,
+{
+    fn index_mut(&mut self, pos: Vec2) -> &mut T {
+        match self.index_of(pos) {
+            Some(index) => &mut self.tiles[index],
+            None => panic!("{:?} is outside of a {:?} map", pos, self.size),
+        }
+    }
+}
+
+impl<A> Extend<(Vec2, A)> for Map<A>
+where
+    A: Clone,
+{
+    fn extend<T: IntoIterator<Item = (Vec2, A)>>(&mut self, iter: T) {
+        for (pos, tile) in iter {
+            self[pos] = tile;
+        }
+    }
+}
+
+impl<T> Map<T>
+where
+    T: Default + Clone,
+{
+    fn new(size: Vec2) -> Self {
+        let num_tiles = size.x * size.y;
+        Self {
+            size,
+            tiles: (0..num_tiles)
+                .map(|_| Default::default())
+                .collect(),
+        }
+    }
+}
+impl<T> Map<T>
+where
+    T: Clone,
+{
+    // index_of() is where the tile at `pos` lives in `tiles`, if it's on the map at all
+    fn index_of(&self, pos: Vec2) -> Option<usize> {
+        if (0..self.size.x).contains(&pos.x) && (0..self.size.y).contains(&pos.y) {
+            Some((pos.x + pos.y * self.size.x) as _)
+        } else {
+            None
+        }
+    }
+
+    // position() is the reverse of index_of()
+    fn position(&self, index: usize) -> Vec2 {
+        Vec2::from_index(index, self.size.x)
+    }
+
+    #[cfg(test)]
+    fn get(&self, pos: Vec2) -> Option<&T> {
+        self.index_of(pos).map(|index| &self.tiles[index])
+    }
+
+    /// Every tile along with its position, row by row
+    pub fn iter_positions(&self) -> impl Iterator<Item = (Vec2, &T)> + '_ {
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(move |(index, tile)| (self.position(index), tile))
+    }
+
+    /// Like `iter_positions`, but the tiles can be changed in place
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Vec2, &mut T)> + '_ {
+        let width = self.size.x;
+        self.tiles
+            .iter_mut()
+            .enumerate()
+            .map(move |(index, tile)| (Vec2::from_index(index, width), tile))
+    }
+
+    fn neighbor_positions(&self, pos: Vec2) -> impl Iterator<Item = Vec2> {
+        (-1..=1)
+            .flat_map(|dx| (-1..=1).map(move |dy| (dx, dy)))
+            .filter(|&(dx, dy)| !(dx == 0 && dy == 0))
+            .map(move |(dx, dy)| Vec2 {
+                x: pos.x + dx,
+                y: pos.y + dy,
+            })
+    }
+}
+
+impl Map<Tile>
+where
+    Tile: Clone,
+{
+    // parse() reads one row per line, all of the same width. A single trailing newline is fine.
+    pub fn parse(input: &[u8]) -> Result<Self, ParseError> {
+        let input = input.strip_suffix(b"\n").unwrap_or(input);
+        if input.is_empty() {
+            return Err(ParseError::Empty);
+        }
+
+        let lines: Vec<&[u8]> = input.split(|&c| c == b'\n').collect();
+        let width = lines[0].len();
+        let mut map = Self::new(Vec2 {
+            x: width as _,
+            y: lines.len() as _,
+        });
+        for (row, line) in lines.iter().enumerate() {
+            if line.is_empty() {
+                return Err(ParseError::EmptyRow { row: row + 1 });
+            }
+            if line.len() != width {
+                return Err(ParseError::RaggedRow {
+                    row: row + 1,
+                    expected: width,
+                    found: line.len(),
+                });
+            }
+            for (col, &byte) in line.iter().enumerate() {
+                let tile = match byte {
+                    b'.' => Tile::Floor,
+                    b'L' => Tile::EmptySeat,
+                    b'#' => Tile::OccupiedSeat,
+                    _ => {
+                        return Err(ParseError::InvalidTile {
+                            row: row + 1,
+                            column: col + 1,
+                            byte,
+                        })
+                    }
+                };
+                map[Vec2 { x: col as _, y: row as _ }] = tile;
+            }
+        }
+        Ok(map)
+    }
+
+    // The neighbours of each seat never change from one generation to the next (seats and floor stay where they
+    // are), so rather than looking around every tile at every step, we work them out once after parsing.
+    fn precompute(&self, neighbours: impl Fn(Vec2) -> Vec<usize>) -> Neighbours {
+        Neighbours {
+            indices: self
+                .iter_positions()
+                .map(|(pos, tile)| match tile {
+                    Tile::Floor => Vec::new(),
+                    _ => neighbours(pos),
+                })
+                .collect(),
+        }
+    }
+
+    // precompute_adjacency() finds the seats right next to each seat, for part 1
+    fn precompute_adjacency(&self) -> Neighbours {
+        self.precompute(|pos| {
+            self.neighbor_positions(pos)
+                .filter_map(|pos| self.index_of(pos))
+                .filter(|&index| self.tiles[index] != Tile::Floor)
+                .collect()
+        })
+    }
+
+    // precompute_visibility() finds the first seat visible in each direction from each seat, for part 2
+    fn precompute_visibility(&self) -> Neighbours {
+        self.precompute(|pos| self.visible_seat_indices(pos).collect())
+    }
+
+    // occupied_neighbours() reads the tiles of the neighbours of the seat at `index`
+    fn occupied_neighbours<'a>(&'a self, neighbours: &'a Neighbours, index: usize) -> impl Iterator<Item = Tile> + 'a {
+        neighbours.of(index).iter().map(move |&index| self.tiles[index])
+    }
+
+    // Each tile's next state only depends on the previous generation, so we can compute them in any order.
+    // step_sequential() goes through them one after the other, `next_tile` being given each tile's index. The
+    // next generation is written into `out`, which must be the same size, and the number of tiles that changed is
+    // returned.
+    #[cfg_attr(feature = "parallel", allow(dead_code))]
+    fn step_sequential(&self, out: &mut Self, next_tile: impl Fn(usize, Tile) -> Tile) -> usize {
+        let mut changed = 0;
+        for (index, (&tile, next)) in self.tiles.iter().zip(out.tiles.iter_mut()).enumerate() {
+            *next = next_tile(index, tile);
+            changed += (*next != tile) as usize;
+        }
+        changed
+    }
+
+    /// Computes the next generation like `step_sequential`, spreading the tiles over rayon's thread pool.
+    /// Each thread writes its tiles straight into `out`.
+    #[cfg(feature = "parallel")]
+    fn step_parallel(&self, out: &mut Self, next_tile: impl Fn(usize, Tile) -> Tile + Sync) -> usize {
+        use rayon::prelude::*;
+
+        out.tiles
+            .par_iter_mut()
+            .enumerate()
+            .map(|(index, next)| {
+                *next = next_tile(index, self.tiles[index]);
+                (*next != self.tiles[index]) as usize
+            })
+            .sum()
+    }
+
+    fn step_tiles(&self, out: &mut Self, next_tile: impl Fn(usize, Tile) -> Tile + Sync) -> usize {
+        debug_assert_eq!(self.size, out.size);
+        #[cfg(feature = "parallel")]
+        return self.step_parallel(out, next_tile);
+        #[cfg(not(feature = "parallel"))]
+        self.step_sequential(out, next_tile)
+    }
+
+    fn neighbours(&self, sight: Sight) -> Neighbours {
+        match sight {
+            Sight::Adjacent => self.precompute_adjacency(),
+            Sight::LineOfSight => self.precompute_visibility(),
+        }
+    }
+
+    /// Writes the next generation into `out`, given the neighbours for `rules.sight`, and returns how many tiles
+    /// changed. Nothing is allocated, so `stabilize` can go back and forth between two maps.
+    fn step_into(&self, out: &mut Self, rules: Rules, neighbours: &Neighbours) -> usize {
+        self.step_tiles(out, |index, tile| tile.next(self.occupied_neighbours(neighbours, index), rules))
+    }
+
+    /// Computes the next generation into a new map. This works out every seat's neighbours first, `stabilize` only
+    /// does that once.
+    #[cfg(test)]
+    fn step(&self, rules: Rules) -> Self {
+        let mut next = self.clone();
+        self.step_into(&mut next, rules, &self.neighbours(rules.sight));
+        next
+    }
+
+    /// Steps until nothing changes anymore, returning the number of generations in which seats changed
+    /// along with the final map. `observe` is shown the starting map as generation 0, then every generation that
+    /// differs from the one before, along with how many tiles changed since that one (none for the starting map).
+    pub fn stabilize(self, rules: Rules, mut observe: impl FnMut(usize, &Self, usize)) -> (usize, Self) {
+        let neighbours = self.neighbours(rules.sight);
+        let mut next = self.clone();
+        let mut map = self;
+        let mut generations = 0;
+        observe(generations, &map, 0);
+        loop {
+            let changed = map.step_into(&mut next, rules, &neighbours);
+            if changed == 0 {
+                return (generations, map);
+            }
+            std::mem::swap(&mut map, &mut next);
+            generations += 1;
+            observe(generations, &map, changed);
+        }
+    }
+
+    pub fn occupied_seats(&self) -> usize {
+        self.tiles
+            .iter()
+            .filter(|&&tile| tile == Tile::OccupiedSeat)
+            .count()
+    }
+
+    /// How many tiles are different in `other`, a map of the same size
+    pub fn changed_tiles(&self, other: &Self) -> usize {
+        self.tiles.iter().zip(&other.tiles).filter(|(a, b)| a != b).count()
+    }
+
+    #[cfg(test)]
+    fn visible_seats(&self, pos: Vec2) -> impl Iterator<Item = Tile> + '_ {
+        self.visible_seat_indices(pos).map(move |index| self.tiles[index])
+    }
+
+    fn visible_seat_indices(&self, pos: Vec2) -> impl Iterator<Item = usize> + '_ {
+        (-1..=1)
+            .flat_map(|dx| (-1..=1).map(move |dy| (dx, dy)))
+            .filter(|&(dx, dy)| !(dx == 0 && dy == 0))
+            .flat_map(move |(dx, dy)| {
+                // keep moving in set direction
+                itertools::iterate(pos, move |v| Vec2 {
+                    x: v.x + dx,
+                    y: v.y + dy,
+                })
+                // required to get the initial value right for our call to itertools::iterate
+                .skip(1)
+                // as long as we're on the map
+                .map(move |pos| self.index_of(pos))
+                .while_some()
+                // and until we reach a seat
+                .find(move |&index| self.tiles[index] != Tile::Floor)
+            })
+    }
+}
+
+/// How many seats end up occupied once people stop moving around, with part 1's rules
+pub fn part1(input: &[u8]) -> Result<usize, ParseError> {
+    Ok(Map::parse(input)?.stabilize(Rules::PART1, |_, _, _| {}).1.occupied_seats())
+}
+
+/// How many seats end up occupied once people stop moving around, with part 2's rules
+pub fn part2(input: &[u8]) -> Result<usize, ParseError> {
+    Ok(Map::parse(input)?.stabilize(Rules::PART2, |_, _, _| {}).1.occupied_seats())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = indoc::indoc!(
+        "
+        L.LL.LL.LL
+        LLLLLLL.LL
+        L.L.L..L..
+        LLLL.LL.LL
+        L.LL.LL.LL
+        L.LLLLL.LL
+        ..L.L.....
+        LLLLLLLLLL
+        L.LLLLLL.L
+        L.LLLLL.LL
+        "
+    );
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(EXAMPLE.as_bytes()), Ok(37));
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(EXAMPLE.as_bytes()), Ok(26));
+    }
+
+    #[test]
+    fn test_neighbor_positions() {
+        use std::collections::HashSet;
+
+        let map = Map::<()>::new(Vec2 { x: 3, y: 3 });
+        let positions: HashSet<_> = map
+            .neighbor_positions(Vec2 { x: 1, y: 1 })
+            .map(|v| (v.x, v.y))
+            .collect();
+        for p in &[(0, 0), (0, 1), (0, 2), (1, 0), (2, 0), (1, 2), (2, 2), (2, 1)] {
+            assert!(positions.contains(p));
+        }
+    }
+
+    #[test]
+    fn test_index_and_iter() {
+        let mut map = Map::<u8>::new(Vec2 { x: 3, y: 2 });
+        map[Vec2 { x: 2, y: 1 }] = 5;
+        map.extend([(Vec2 { x: 0, y: 0 }, 1), (Vec2 { x: 1, y: 1 }, 4)]);
+        for (pos, tile) in map.iter_mut() {
+            *tile += pos.x as u8;
+        }
+        assert_eq!(map[Vec2 { x: 2, y: 1 }], 7);
+        assert_eq!(map.get(Vec2 { x: 3, y: 0 }), None);
+
+        let tiles: Vec<_> = map.iter_positions().map(|(pos, &tile)| (pos.x, pos.y, tile)).collect();
+        assert_eq!(
+            tiles,
+            [(0, 0, 1), (1, 0, 1), (2, 0, 2), (0, 1, 0), (1, 1, 5), (2, 1, 7)]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_out_of_bounds() {
+        let map = Map::<u8>::new(Vec2 { x: 3, y: 2 });
+        let _ = map[Vec2 { x: 0, y: 2 }];
+    }
+
+    #[test]
+    fn test_visible_seats() {
+        let map = Map::<Tile>::parse(
+            indoc::indoc!(
+                "
+                .......#.
+                ...#.....
+                .#.......
+                .........
+                ..#L....#
+                ....#....
+                .........
+                #........
+                ...#.....
+                "
+            )
+            .trim()
+            .as_bytes(),
+        )
+        .unwrap();
+        println!("{:?}", map);
+        assert_eq!(map.visible_seats(Vec2 { x: 3, y: 4 }).count(), 8);
+        assert_eq!(map.visible_seats(Vec2 { x: 8, y: 0 }).count(), 2);
+    }
+
+    #[test]
+    fn test_visible_seats2() {
+        let map = Map::<Tile>::parse(
+            indoc::indoc!(
+                "
+                .##.##.
+                #.#.#.#
+                ##...##
+                ...L...
+                ##...##
+                #.#.#.#
+                .##.##.
+                "
+            )
+            .trim()
+            .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(map.visible_seats(Vec2 { x: 3, y: 3 }).count(), 0);
+    }
+
+    #[test]
+    fn test_example() {
+        let map = Map::<Tile>::parse(include_bytes!("sample.txt")).unwrap();
+        let (generations, last) = map.clone().stabilize(Rules::PART1, |_, _, _| {});
+        assert_eq!((generations, last.occupied_seats()), (5, 37));
+        let (generations, last) = map.clone().stabilize(Rules::PART2, |_, _, _| {});
+        assert_eq!((generations, last.occupied_seats()), (6, 26));
+
+        // stepping one generation at a time gets to the same place
+        let mut stepped = map;
+        for _ in 0..6 {
+            stepped = stepped.step(Rules::PART2);
+        }
+        assert_eq!(stepped.occupied_seats(), 26);
+    }
+
+    #[test]
+    fn test_stabilize_observer() {
+        // occupied seats after each round of the puzzle's walkthroughs, starting from the all-empty layout
+        let record = |rules| {
+            let mut seen = Vec::new();
+            let map = Map::<Tile>::parse(include_bytes!("sample.txt")).unwrap();
+            let mut previous = map.clone();
+            let (generations, _) = map.stabilize(rules, |generation, map, changed| {
+                assert_eq!(changed, previous.changed_tiles(map), "generation {}", generation);
+                previous = map.clone();
+                seen.push((generation, map.occupied_seats()))
+            });
+            (generations, seen)
+        };
+        assert_eq!(
+            record(Rules::PART1),
+            (5, vec![(0, 0), (1, 71), (2, 20), (3, 51), (4, 30), (5, 37)])
+        );
+        assert_eq!(
+            record(Rules::PART2),
+            (6, vec![(0, 0), (1, 71), (2, 7), (3, 53), (4, 18), (5, 31), (6, 26)])
+        );
+    }
+
+    #[test]
+    fn test_step_into() {
+        // going back and forth between two buffers ends up where allocating a new map every generation does, with
+        // step_into() counting exactly the tiles that differ
+        for (rules, occupied) in [(Rules::PART1, 37), (Rules::PART2, 26)] {
+            let mut map = Map::<Tile>::parse(include_bytes!("sample.txt")).unwrap();
+            let neighbours = map.neighbours(rules.sight);
+            let mut next = map.clone();
+            loop {
+                let changed = map.step_into(&mut next, rules, &neighbours);
+                let stepped = map.step(rules);
+                assert_eq!(next, stepped);
+                assert_eq!(changed, map.changed_tiles(&stepped));
+                if changed == 0 {
+                    break;
+                }
+                std::mem::swap(&mut map, &mut next);
+            }
+            assert_eq!(map.occupied_seats(), occupied);
+        }
+    }
+
+    #[test]
+    fn test_precompute() {
+        let map = Map::<Tile>::parse(include_bytes!("sample.txt")).unwrap();
+        let adjacency = map.precompute_adjacency();
+        let visibility = map.precompute_visibility();
+        // the top left seat has seats below it and down and to the right, but floor to its right, past which it can
+        // see another seat
+        assert_eq!(adjacency.of(0), &[10, 11]);
+        assert_eq!(visibility.of(0), &[10, 2, 11]);
+        // floor has no neighbours at all
+        assert!(adjacency.of(1).is_empty() && visibility.of(1).is_empty());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_step_parallel() {
+        // steps both ways to the fixed point, checking they agree on every generation
+        fn compare(map: &Map<Tile>, rules: Rules) {
+            let neighbours = map.neighbours(rules.sight);
+            let next_tile =
+                |map: &Map<Tile>, index, tile: Tile| tile.next(map.occupied_neighbours(&neighbours, index), rules);
+            let (mut sequential, mut parallel) = (map.clone(), map.clone());
+            let (mut next_sequential, mut next_parallel) = (map.clone(), map.clone());
+            loop {
+                let changed = sequential.step_sequential(&mut next_sequential, |index, tile| {
+                    next_tile(&sequential, index, tile)
+                });
+                let changed_parallel =
+                    parallel.step_parallel(&mut next_parallel, |index, tile| next_tile(&parallel, index, tile));
+                assert_eq!((changed, &next_sequential), (changed_parallel, &next_parallel));
+                if changed == 0 {
+                    break;
+                }
+                std::mem::swap(&mut sequential, &mut next_sequential);
+                std::mem::swap(&mut parallel, &mut next_parallel);
+            }
+        }
+
+        let map = Map::<Tile>::parse(include_bytes!("sample.txt")).unwrap();
+        compare(&map, Rules::PART1);
+        compare(&map, Rules::PART2);
+    }
+
+    #[test]
+    fn test_parse_trailing_newline() {
+        let map = Map::<Tile>::parse(b"L.#\n#.L\n").unwrap();
+        assert_eq!(map.size, Vec2 { x: 3, y: 2 });
+        assert_eq!(map, Map::<Tile>::parse(b"L.#\n#.L").unwrap());
+
+        // only one though: anything more is a blank row
+        assert_eq!(Map::<Tile>::parse(b"L.#\n#.L\n\n"), Err(ParseError::EmptyRow { row: 3 }));
+        assert_eq!(Map::<Tile>::parse(b"\n"), Err(ParseError::Empty));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Map::<Tile>::parse(b"L.#\n#.L\n\nLLL"),
+            Err(ParseError::EmptyRow { row: 3 })
+        );
+        assert_eq!(
+            Map::<Tile>::parse(b"L.#\n#.L\nLL"),
+            Err(ParseError::RaggedRow {
+                row: 3,
+                expected: 3,
+                found: 2
+            })
+        );
+        let err = Map::<Tile>::parse(b"L.#\n#X.").unwrap_err();
+        assert_eq!(
+            err,
+            ParseError::InvalidTile {
+                row: 2,
+                column: 2,
+                byte: b'X'
+            }
+        );
+        assert_eq!(err.to_string(), "row 2, column 2: expected '.', 'L' or '#', but got 'X'");
+    }
+}
@@ -1,38 +1,64 @@
+use anyhow::Context;
 use derive_more::*;
+use itertools::Itertools;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Add, Sub)]
+// Coordinates are i64 whatever the platform, and every operation that could overflow them is checked, returning
+// None rather than a wrapped-around position.
 struct Vec2 {
-    x: isize,
-    y: isize,
+    x: i64,
+    y: i64,
 }
 
 impl Vec2 {
-    // Vec2 is copy, so it's fine to take `self`
-    fn manhattan(self) -> usize {
-        (self.x.abs() + self.y.abs()) as _
+    // Vec2 is copy, so it's fine to take `self`. This saturates rather than overflow, which only happens for
+    // distances longer than u64::MAX.
+    fn manhattan(self) -> u64 {
+        self.x.unsigned_abs().saturating_add(self.y.unsigned_abs())
     }
 
-    fn rotate(self, d: AngleDelta) -> Self {
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        Some(Self {
+            x: self.x.checked_add(rhs.x)?,
+            y: self.y.checked_add(rhs.y)?,
+        })
+    }
+
+    fn rotate(self, d: AngleDelta) -> Option<Self> {
+        self.rotate_quarter_turns(d.0)
+    }
+
+    // Rotates around the origin, clockwise for positive `n` (north becomes east) and counter-clockwise for
+    // negative `n`. Any number of turns works, 5 is the same as 1 and -1 the same as 3.
+    fn rotate_quarter_turns(self, n: isize) -> Option<Self> {
         let Self { x, y } = self;
-        match d.0.rem_euclid(4) {
+        Some(match n.rem_euclid(4) {
             0 => Self { x, y },
-            1 => Self { x: y, y: -x },
-            2 => Self { x: -x, y: -y },
-            3 => Self { x: -y, y: x },
+            1 => Self {
+                x: y,
+                y: x.checked_neg()?,
+            },
+            2 => Self {
+                x: x.checked_neg()?,
+                y: y.checked_neg()?,
+            },
+            3 => Self {
+                x: y.checked_neg()?,
+                y: x,
+            },
             _ => unreachable!(),
-        }
+        })
     }
-}
 
-// we often move several units in some direction... so it'd be neat to multiply a Vec2 by an isize
-impl std::ops::Mul<isize> for Vec2 {
-    type Output = Self;
-
-    fn mul(self, rhs: isize) -> Self::Output {
-        Self {
-            x: self.x * rhs,
-            y: self.y * rhs,
-        }
+    // we often move several units in some direction... so it's neat to be able to scale a Vec2
+    fn scale(self, factor: i64) -> Option<Self> {
+        Some(Self {
+            x: self.x.checked_mul(factor)?,
+            y: self.y.checked_mul(factor)?,
+        })
     }
 }
 
@@ -48,9 +74,9 @@ enum Direction {
 }
 
 // We can easily convert a Direction to an isize, because _any_ Direction is always a valid isize
-impl Into<isize> for Direction {
-    fn into(self) -> isize {
-        self as _
+impl From<Direction> for isize {
+    fn from(dir: Direction) -> isize {
+        dir as _
     }
 }
 
@@ -60,14 +86,13 @@ impl std::convert::TryFrom<isize> for Direction {
 
     fn try_from(value: isize) -> Result<Self, Self::Error> {
         if (0..=3).contains(&value) {
-            Ok(unsafe { std::mem::transmute(value as u8) })
+            Ok(unsafe { std::mem::transmute::<u8, Direction>(value as u8) })
         } else {
             Err("direction out of bounds!")
         }
     }
 }
 
-
 impl Direction {
     fn vec(self) -> Vec2 {
         match self {
@@ -83,6 +108,21 @@ impl Direction {
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 struct AngleDelta(isize);
 
+impl AngleDelta {
+    // normalized() is the same angle as a number of right turns in 0..4, so L90 becomes R270
+    fn normalized(self) -> Self {
+        Self(self.0.rem_euclid(4))
+    }
+}
+
+impl std::ops::Add<AngleDelta> for AngleDelta {
+    type Output = Self;
+
+    fn add(self, rhs: AngleDelta) -> Self::Output {
+        Self(self.0 + rhs.0)
+    }
+}
+
 // if the angle is 90, then
 // * If facing East, now facing South  * If facing South, now facing West
 // * If facing West, now facing North  * If facing North, now facing East
@@ -93,8 +133,8 @@ impl std::ops::Add<AngleDelta> for Direction {
     fn add(self, rhs: AngleDelta) -> Self::Output {
         use std::convert::TryInto;
 
-        let angle: isize = self.into();
-        (angle + rhs.0).rem_euclid(4).try_into().unwrap()
+        let angle = AngleDelta(self.into());
+        (angle + rhs).normalized().0.try_into().unwrap()
     }
 }
 
@@ -104,15 +144,36 @@ struct ShipState1 {
     dir: Direction,
 }
 
+impl ShipState1 {
+    // the ship starts facing east
+    const START: Self = Self {
+        pos: Vec2 { x: 0, y: 0 },
+        dir: Direction::East,
+    };
+}
+
+/// Following an instruction would take the ship, or its waypoint, further than an i64 coordinate can go
+#[derive(thiserror::Error, Debug, PartialEq)]
+enum NavigationError {
+    #[error("coordinates overflow while following {0}")]
+    Overflow(Instruction),
+}
+
 // really nice impl to leverage with fold... imagine we start with initial state,
 // and keep applying modifications to it, from each instruction yielded by an iterator
 impl std::ops::Add<Instruction> for ShipState1 {
-    type Output = Self;
+    type Output = Result<Self, NavigationError>;
 
     fn add(self, rhs: Instruction) -> Self::Output {
-        match rhs {
+        let moved = |dir: Direction, units| {
+            let delta = dir.vec().scale(units);
+            delta
+                .and_then(|delta| self.pos.checked_add(delta))
+                .ok_or(NavigationError::Overflow(rhs))
+        };
+        Ok(match rhs {
             Instruction::Move(dir, units) => Self {
-                pos: self.pos + dir.vec() * units,
+                pos: moved(dir, units)?,
                 ..self
             },
             Instruction::Rotate(delta) => Self {
@@ -120,105 +181,352 @@ impl std::ops::Add<Instruction> for ShipState1 {
                 ..self
             },
             Instruction::Advance(units) => Self {
-                pos: self.pos + self.dir.vec() * units,
+                pos: moved(self.dir, units)?,
                 ..self
             },
-        }
+        })
     }
 }
 
+/// For part 2, most instructions move a waypoint around, relative to the ship, and the ship only ever
+/// moves towards it
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
-struct ShipState2 {
-    pos: Vec2,
-    dir: Direction,
+struct WaypointState {
+    ship: Vec2,
     waypoint: Vec2,
 }
 
-// really nice impl to leverage with fold... imagine we start with initial state,
-// and keep applying modifications to it, from each instruction yielded by an iterator
-impl std::ops::Add<Instruction> for ShipState2 {
-    type Output = Self;
+impl WaypointState {
+    // the waypoint starts 10 units east and 1 unit north of the ship
+    const START: Self = Self {
+        ship: Vec2 { x: 0, y: 0 },
+        waypoint: Vec2 { x: 10, y: 1 },
+    };
+}
+
+impl std::ops::Add<Instruction> for WaypointState {
+    type Output = Result<Self, NavigationError>;
 
     fn add(self, rhs: Instruction) -> Self::Output {
-        match rhs {
+        let checked = |v: Option<Vec2>| v.ok_or(NavigationError::Overflow(rhs));
+        Ok(match rhs {
             // moves waypoint
             Instruction::Move(dir, units) => Self {
-                waypoint: self.waypoint + dir.vec() * units,
+                waypoint: checked(
+                    dir.vec()
+                        .scale(units)
+                        .and_then(|delta| self.waypoint.checked_add(delta)),
+                )?,
                 ..self
             },
-            // rotates waypoint (relative to ship)
+            // rotates waypoint around the ship, (x, y) becoming (y, -x) for every quarter turn to the right
             Instruction::Rotate(delta) => Self {
-                waypoint: self.waypoint.rotate(delta),
+                waypoint: checked(self.waypoint.rotate(delta))?,
                 ..self
             },
-            // advanced towards waypoint
+            // moves the ship to the waypoint `units` times, the waypoint moving along with it
             Instruction::Advance(units) => Self {
-                pos: self.pos + self.waypoint * units,
+                ship: checked(
+                    self.waypoint
+                        .scale(units)
+                        .and_then(|delta| self.ship.checked_add(delta)),
+                )?,
                 ..self
             },
-        }
+        })
     }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum Instruction {
     /// Moves in given direction
-    Move(Direction, isize),
+    Move(Direction, i64),
     /// Turns
     Rotate(AngleDelta),
     /// Moves forward
-    Advance(isize),
-}
-
-fn parse_instructions(input: &str) -> impl Iterator<Item = Instruction> + '_ {
-    input.lines().map(|line| {
-        let command = line.as_bytes()[0];
-        // Safety: this will panic if `line` starts with multibyte character
-        let number: isize = (&line[1..]).parse().unwrap();
-
-        match command {
-            b'N' => Instruction::Move(Direction::North, number),
-            b'S' => Instruction::Move(Direction::South, number),
-            b'E' => Instruction::Move(Direction::East, number),
-            b'W' => Instruction::Move(Direction::West, number),
-            b'L' => Instruction::Rotate(AngleDelta(-number / 90)),
-            b'R' => Instruction::Rotate(AngleDelta(number / 90)),
-            b'F' => Instruction::Advance(number),
-            c => panic!("unknown instruction {}", c as char),
+    Advance(i64),
+}
+
+// Display writes an instruction the way it appears in the input, e.g. `N3`, `R90` or `F11`
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Instruction::Move(dir, units) => {
+                let command = match dir {
+                    Direction::North => 'N',
+                    Direction::South => 'S',
+                    Direction::East => 'E',
+                    Direction::West => 'W',
+                };
+                write!(f, "{}{}", command, units)
+            }
+            // quarter turns back to degrees, left being negative
+            Instruction::Rotate(AngleDelta(turns)) if turns < 0 => write!(f, "L{}", -turns * 90),
+            Instruction::Rotate(AngleDelta(turns)) => write!(f, "R{}", turns * 90),
+            Instruction::Advance(units) => write!(f, "F{}", units),
         }
+    }
+}
+
+// render() is the reverse of parse_instructions(), one instruction per line
+#[cfg(test)]
+fn render(instructions: &[Instruction]) -> String {
+    instructions.iter().map(|ins| format!("{}\n", ins)).collect()
+}
+
+/// A problem with one line of the navigation instructions. Line numbers are 1-based.
+#[derive(thiserror::Error, Debug, PartialEq)]
+enum ParseError {
+    #[error("line {line}: expected an instruction")]
+    EmptyLine { line: usize },
+    #[error("line {line}: unknown command {command:?}")]
+    UnknownCommand { line: usize, command: char },
+    #[error("line {line}: expected a number, got {text:?}")]
+    BadNumber { line: usize, text: String },
+    #[error("line {line}: can only turn by multiples of 90°, not {degrees}°")]
+    BadAngle { line: usize, degrees: i64 },
+}
+
+fn parse_instruction(line: usize, text: &str) -> Result<Instruction, ParseError> {
+    // splitting off a char rather than a byte, so lines starting with a multibyte character are reported too
+    let mut chars = text.chars();
+    let command = chars.next().ok_or(ParseError::EmptyLine { line })?;
+    let operand = chars.as_str();
+    let bad_number = || ParseError::BadNumber {
+        line,
+        text: operand.to_string(),
+    };
+    let number: i64 = operand.parse().map_err(|_| bad_number())?;
+    let quarter_turns = || match number % 90 {
+        0 => isize::try_from(number / 90).map_err(|_| bad_number()),
+        _ => Err(ParseError::BadAngle { line, degrees: number }),
+    };
+
+    Ok(match command {
+        'N' => Instruction::Move(Direction::North, number),
+        'S' => Instruction::Move(Direction::South, number),
+        'E' => Instruction::Move(Direction::East, number),
+        'W' => Instruction::Move(Direction::West, number),
+        'L' => Instruction::Rotate(AngleDelta(-quarter_turns()?)),
+        'R' => Instruction::Rotate(AngleDelta(quarter_turns()?)),
+        'F' => Instruction::Advance(number),
+        command => return Err(ParseError::UnknownCommand { line, command }),
     })
 }
 
-fn main() {
-    /*
-    for ins in parse_instructions(include_str!("input.txt")) {
-        println!("{:?}", ins);
+// parse_instructions() reads one instruction per line, stopping at the first bad one
+fn parse_instructions(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, text)| parse_instruction(index + 1, text))
+        .collect()
+}
+
+/// A way of reading the navigation instructions: part 1 sails in the direction the ship is facing, part 2
+/// towards a waypoint.
+trait Navigate: Sized {
+    fn start() -> Self;
+    fn apply(self, ins: Instruction) -> Result<Self, NavigationError>;
+    /// Where the ship is
+    fn position(&self) -> Vec2;
+}
+
+impl Navigate for ShipState1 {
+    fn start() -> Self {
+        Self::START
     }
-    */
 
-    let start = ShipState1 {
-        dir: Direction::East,
-        pos: Vec2 { x: 0, y: 0 },
+    fn apply(self, ins: Instruction) -> Result<Self, NavigationError> {
+        self + ins
+    }
+
+    fn position(&self) -> Vec2 {
+        self.pos
+    }
+}
+
+impl Navigate for WaypointState {
+    fn start() -> Self {
+        Self::START
+    }
+
+    fn apply(self, ins: Instruction) -> Result<Self, NavigationError> {
+        self + ins
+    }
+
+    fn position(&self) -> Vec2 {
+        self.ship
+    }
+}
+
+// states() yields the starting state, then the state after each instruction. The last one is where we end up,
+// unless an instruction overflows, in which case its error is the last item.
+fn states<N>(instructions: &[Instruction]) -> impl Iterator<Item = Result<N, NavigationError>> + '_
+where
+    N: Navigate + Copy + 'static,
+{
+    std::iter::once(Ok(N::start())).chain(instructions.iter().scan(Some(N::start()), |state, &ins| {
+        let next = state.take()?.apply(ins);
+        if let Ok(next) = &next {
+            *state = Some(*next);
+        }
+        Some(next)
+    }))
+}
+
+// sail() is where the ship ends up after following all of the instructions
+fn sail<N>(instructions: &[Instruction]) -> Result<Vec2, NavigationError>
+where
+    N: Navigate,
+{
+    let end = instructions
+        .iter()
+        .try_fold(N::start(), |state, &ins| state.apply(ins))?;
+    Ok(end.position())
+}
+
+// distance() is how far the ship ends up from where it started, the origin
+fn distance<N>(instructions: &[Instruction]) -> Result<u64, NavigationError>
+where
+    N: Navigate,
+{
+    Ok(sail::<N>(instructions)?.manhattan())
+}
+
+// part1() is how far the ship ends up from where it started, sailing in the direction it's facing
+fn part1(instructions: &[Instruction]) -> Result<u64, NavigationError> {
+    distance::<ShipState1>(instructions)
+}
+
+// part2() is how far the ship ends up from where it started, sailing towards the waypoint
+fn part2(instructions: &[Instruction]) -> Result<u64, NavigationError> {
+    distance::<WaypointState>(instructions)
+}
+
+// read_instructions() parses everything `reader` has to offer, be it a file, stdin or the embedded input
+fn read_instructions(mut reader: impl Read) -> anyhow::Result<Vec<Instruction>> {
+    let mut input = String::new();
+    reader
+        .read_to_string(&mut input)
+        .context("couldn't read the instructions")?;
+    Ok(parse_instructions(&input)?)
+}
+
+/// Draws the ship's path in a `width` by `height` grid of characters (north is up), squeezing its bounding box to
+/// fit. `S` marks the start, `E` the end, and `*` every cell the ship went through.
+fn plot(path: &[Vec2], width: usize, height: usize) -> String {
+    let (first, last) = match (path.first(), path.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return String::new(),
+    };
+    let (min_x, max_x) = path.iter().map(|v| v.x).minmax().into_option().unwrap();
+    let (min_y, max_y) = path.iter().map(|v| v.y).minmax().into_option().unwrap();
+    // scale to cells as floats, so consecutive positions can be joined up
+    let cell = |v: Vec2| {
+        // as floats from the start, so huge coordinates can't overflow
+        let scale = |n: i64, min: i64, max: i64, cells: usize| {
+            (n as f64 - min as f64) * (cells - 1) as f64 / (max as f64 - min as f64).max(1.0)
+        };
+        (scale(v.x, min_x, max_x, width), scale(-v.y, -max_y, -min_y, height))
     };
-    let end = parse_instructions(include_str!("input.txt")).fold(start, |state, ins| state + ins);
 
-    //dbg!(start, end);
-    println!("Part 1:");
-    println!("  {}", (end.pos - start.pos).manhattan());
+    let mut grid = vec![vec![' '; width]; height];
+    for (&from, &to) in path.iter().tuple_windows() {
+        let ((x0, y0), (x1, y1)) = (cell(from), cell(to));
+        let steps = (x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let t = step as f64 / steps as f64;
+            let (x, y) = (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t);
+            grid[y.round() as usize][x.round() as usize] = '*';
+        }
+    }
+    for (v, c) in [(first, 'S'), (last, 'E')] {
+        let (x, y) = cell(v);
+        grid[y.round() as usize][x.round() as usize] = c;
+    }
 
-    let start2 = ShipState2 {
-        dir: Direction::East,
-        pos: Vec2 { x: 0, y: 0 },
-        waypoint: Vec2 { x: 10, y: 1 },
+    grid.into_iter()
+        .map(|row| row.into_iter().collect::<String>().trim_end().to_string() + "\n")
+        .collect()
+}
+
+fn main() -> anyhow::Result<()> {
+    let (mut path, mut trace, mut verbose) = (None, false, false);
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            // prints every state the ship goes through, and a plot of its path
+            "--trace" => trace = true,
+            // prints the instructions as they were parsed
+            "--verbose" => verbose = true,
+            _ if !arg.starts_with("--") => path = Some(arg),
+            _ => anyhow::bail!("usage: day12 [--trace] [--verbose] [PATH | -]"),
+        }
+    }
+
+    // `day12 <path>` reads the instructions from a file and `day12 -` from stdin,
+    // without arguments we use the embedded input.txt
+    let instructions = match path.as_deref() {
+        None => read_instructions(include_str!("input.txt").as_bytes())?,
+        Some("-") => read_instructions(std::io::stdin())?,
+        Some(path) => read_instructions(File::open(path).with_context(|| format!("couldn't open {}", path))?)?,
     };
-    let end2 = parse_instructions(include_str!("input.txt")).fold(start2, |state, ins| state + ins);
-    //dbg!(start2, end2);
+
+    if verbose {
+        for ins in &instructions {
+            println!("{:?}", ins);
+        }
+    }
+
+    if trace {
+        let path: Vec<ShipState1> = states(&instructions).collect::<Result<_, _>>()?;
+        println!("Part 1 trace:");
+        println!("{:>5}  {:<12} {:>14}  heading", "step", "instruction", "position");
+        for (step, (state, ins)) in path
+            .iter()
+            .zip(std::iter::once(None).chain(instructions.iter().map(Some)))
+            .enumerate()
+        {
+            let ins = ins.map_or(String::new(), |ins| ins.to_string());
+            let pos = format!("({}, {})", state.pos.x, state.pos.y);
+            println!("{:>5}  {:<12} {:>14}  {:?}", step, ins, pos, state.dir);
+        }
+        print!(
+            "{}",
+            plot(&path.iter().map(|state| state.pos).collect::<Vec<_>>(), 60, 30)
+        );
+
+        let path: Vec<WaypointState> = states(&instructions).collect::<Result<_, _>>()?;
+        println!("Part 2 trace:");
+        println!(
+            "{:>5}  {:<12} {:>14}  {:>14}",
+            "step", "instruction", "ship", "waypoint"
+        );
+        for (step, (state, ins)) in path
+            .iter()
+            .zip(std::iter::once(None).chain(instructions.iter().map(Some)))
+            .enumerate()
+        {
+            let ins = ins.map_or(String::new(), |ins| ins.to_string());
+            let ship = format!("({}, {})", state.ship.x, state.ship.y);
+            let waypoint = format!("({}, {})", state.waypoint.x, state.waypoint.y);
+            println!("{:>5}  {:<12} {:>14}  {:>14}", step, ins, ship, waypoint);
+        }
+        print!(
+            "{}",
+            plot(&path.iter().map(|state| state.ship).collect::<Vec<_>>(), 60, 30)
+        );
+    }
+
+    println!("Part 1:");
+    println!("  {}", part1(&instructions)?);
+
     println!("Part 2:");
-    println!("  {}", (end2.pos - start2.pos).manhattan())
+    println!("  {}", part2(&instructions)?);
 
+    Ok(())
 }
 
-
 #[test]
 fn vec2_add() {
     let a = Vec2 { x: 3, y: 8 };
@@ -236,27 +544,21 @@ fn manhattan_example() {
 #[test]
 fn test_rotate() {
     let v = Vec2 { x: 3, y: 1 };
-    assert_eq!(v.rotate(AngleDelta(0)), v);
-    assert_eq!(v.rotate(AngleDelta(4)), v);
-    assert_eq!(v.rotate(AngleDelta(-4)), v);
+    assert_eq!(v.rotate(AngleDelta(0)), Some(v));
+    assert_eq!(v.rotate(AngleDelta(4)), Some(v));
+    assert_eq!(v.rotate(AngleDelta(-4)), Some(v));
 
-    assert_eq!(v.rotate(AngleDelta(1)), Vec2 { x: 1, y: -3 });
-    assert_eq!(v.rotate(AngleDelta(2)), Vec2 { x: -3, y: -1 });
-    assert_eq!(v.rotate(AngleDelta(3)), Vec2 { x: -1, y: 3 });
+    assert_eq!(v.rotate(AngleDelta(1)), Some(Vec2 { x: 1, y: -3 }));
+    assert_eq!(v.rotate(AngleDelta(2)), Some(Vec2 { x: -3, y: -1 }));
+    assert_eq!(v.rotate(AngleDelta(3)), Some(Vec2 { x: -1, y: 3 }));
 }
 
 #[test]
 fn direction_try_from() {
     use std::convert::TryFrom;
 
-    assert_eq!(
-        <Direction as TryFrom<isize>>::try_from(0).unwrap(),
-        Direction::East
-    );
-    assert_eq!(
-        <Direction as TryFrom<isize>>::try_from(2).unwrap(),
-        Direction::West
-    );
+    assert_eq!(<Direction as TryFrom<isize>>::try_from(0).unwrap(), Direction::East);
+    assert_eq!(<Direction as TryFrom<isize>>::try_from(2).unwrap(), Direction::West);
     assert!(<Direction as TryFrom<isize>>::try_from(-1).is_err(),);
     assert!(<Direction as TryFrom<isize>>::try_from(4).is_err(),);
 }
@@ -271,3 +573,262 @@ fn test_direction_add() {
     assert_eq!(Direction::East + AngleDelta(4), Direction::East);
 }
 
+#[test]
+fn test_example() {
+    let instructions = parse_instructions(include_str!("sample.txt")).unwrap();
+    assert_eq!(part1(&instructions), Ok(25));
+    assert_eq!(part2(&instructions), Ok(286));
+}
+
+#[test]
+fn test_waypoint_steps() {
+    // the walkthrough from the puzzle, one instruction at a time
+    let steps = [
+        (Instruction::Advance(10), (100, 10), (10, 1)),
+        (Instruction::Move(Direction::North, 3), (100, 10), (10, 4)),
+        (Instruction::Advance(7), (170, 38), (10, 4)),
+        (Instruction::Rotate(AngleDelta(1)), (170, 38), (4, -10)),
+        (Instruction::Advance(11), (214, -72), (4, -10)),
+    ];
+    let mut state = WaypointState::START;
+    for (ins, (x, y), (wx, wy)) in steps {
+        state = (state + ins).unwrap();
+        assert_eq!(state.ship, Vec2 { x, y });
+        assert_eq!(state.waypoint, Vec2 { x: wx, y: wy });
+    }
+}
+
+#[test]
+fn test_parse_crlf() {
+    let expected = parse_instructions(include_str!("sample.txt")).unwrap();
+    assert_eq!(parse_instructions("F10\r\nN3\r\nF7\r\nR90\r\nF11\r\n"), Ok(expected));
+    assert_eq!(
+        parse_instructions("L270\nR180"),
+        Ok(vec![
+            Instruction::Rotate(AngleDelta(-3)),
+            Instruction::Rotate(AngleDelta(2))
+        ])
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(parse_instructions("F10\n\nN3"), Err(ParseError::EmptyLine { line: 2 }));
+    assert_eq!(
+        parse_instructions("F10\nX3"),
+        Err(ParseError::UnknownCommand { line: 2, command: 'X' })
+    );
+    assert_eq!(
+        parse_instructions("é10"),
+        Err(ParseError::UnknownCommand { line: 1, command: 'é' })
+    );
+    assert_eq!(
+        parse_instructions("N3\nFfoo"),
+        Err(ParseError::BadNumber {
+            line: 2,
+            text: "foo".to_string()
+        })
+    );
+    assert_eq!(
+        parse_instructions("R"),
+        Err(ParseError::BadNumber {
+            line: 1,
+            text: "".to_string()
+        })
+    );
+    // used to be silently integer-divided to no turn at all
+    assert_eq!(
+        parse_instructions("L45"),
+        Err(ParseError::BadAngle { line: 1, degrees: 45 })
+    );
+}
+
+#[test]
+fn test_states() {
+    let instructions = parse_instructions(include_str!("sample.txt")).unwrap();
+
+    let path: Vec<ShipState1> = states(&instructions).collect::<Result<_, _>>().unwrap();
+    assert_eq!(path.len(), instructions.len() + 1);
+    assert_eq!(path[0], ShipState1::START);
+    let end = instructions
+        .iter()
+        .try_fold(ShipState1::START, |state, &ins| state + ins)
+        .unwrap();
+    assert_eq!(path.last(), Some(&end));
+
+    let path: Vec<WaypointState> = states(&instructions).collect::<Result<_, _>>().unwrap();
+    assert_eq!(path.len(), instructions.len() + 1);
+    let end = instructions
+        .iter()
+        .try_fold(WaypointState::START, |state, &ins| state + ins)
+        .unwrap();
+    assert_eq!(path.last(), Some(&end));
+}
+
+#[test]
+fn test_plot() {
+    // east 4, then north 2: the path fits exactly in a 5 by 3 grid
+    let path = [Vec2 { x: 0, y: 0 }, Vec2 { x: 4, y: 0 }, Vec2 { x: 4, y: 2 }];
+    assert_eq!(plot(&path, 5, 3), "    E\n    *\nS****\n");
+    assert_eq!(plot(&[], 5, 3), "");
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(Instruction::Move(Direction::North, 3).to_string(), "N3");
+    assert_eq!(Instruction::Rotate(AngleDelta(1)).to_string(), "R90");
+    assert_eq!(Instruction::Rotate(AngleDelta(-3)).to_string(), "L270");
+    assert_eq!(Instruction::Advance(11).to_string(), "F11");
+    assert_eq!(
+        render(&parse_instructions(include_str!("sample.txt")).unwrap()),
+        "F10\nN3\nF7\nR90\nF11\n"
+    );
+}
+
+#[test]
+fn test_render_round_trip() {
+    use rand_xoshiro::rand_core::{RngCore, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    let parsed = parse_instructions(include_str!("input.txt")).unwrap();
+    assert_eq!(parse_instructions(&render(&parsed)), Ok(parsed));
+
+    // a seeded generator, so the instructions are the same on every run
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(0x2545_f491_4f6c_dd1d);
+    let mut random = move |n: u64| rng.next_u64() % n;
+    let directions = [Direction::North, Direction::South, Direction::East, Direction::West];
+    let instructions: Vec<_> = (0..1000)
+        .map(|_| match random(3) {
+            0 => Instruction::Move(directions[random(4) as usize], random(1000) as i64 - 500),
+            1 => Instruction::Rotate(AngleDelta(random(15) as isize - 7)),
+            _ => Instruction::Advance(random(1000) as i64),
+        })
+        .collect();
+    assert_eq!(parse_instructions(&render(&instructions)), Ok(instructions));
+}
+
+#[test]
+fn test_sail() {
+    let instructions = parse_instructions(include_str!("sample.txt")).unwrap();
+    assert_eq!(sail::<ShipState1>(&instructions), Ok(Vec2 { x: 17, y: -8 }));
+    assert_eq!(distance::<ShipState1>(&instructions), Ok(25));
+    assert_eq!(sail::<WaypointState>(&instructions), Ok(Vec2 { x: 214, y: -72 }));
+    assert_eq!(distance::<WaypointState>(&instructions), Ok(286));
+}
+
+#[test]
+fn test_read_instructions() {
+    let instructions = read_instructions("F10\nN3\nF7\nR90\nF11\n".as_bytes()).unwrap();
+    assert_eq!(instructions, parse_instructions(include_str!("sample.txt")).unwrap());
+
+    // parse errors come through as they are, for main to report
+    let err = read_instructions("F10\nL45\n".as_bytes()).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ParseError>(),
+        Some(&ParseError::BadAngle { line: 2, degrees: 45 })
+    );
+}
+
+#[test]
+fn test_rotate_quarter_turns() {
+    // one right turn, then all the others in terms of it: the way the puzzle turns R90 with the waypoint
+    // 10 east and 4 north into 4 east and 10 south
+    assert_eq!(
+        Vec2 { x: 10, y: 4 }.rotate_quarter_turns(1),
+        Some(Vec2 { x: 4, y: -10 })
+    );
+    let right = |v: Vec2| Vec2 { x: v.y, y: -v.x };
+
+    let vectors = [
+        Vec2 { x: 10, y: 4 },
+        Vec2 { x: -3, y: 7 },
+        Vec2 { x: -5, y: -2 },
+        Vec2 { x: 6, y: -1 },
+        Vec2 { x: 0, y: 9 },
+        Vec2 { x: 0, y: 0 },
+    ];
+    for v in vectors {
+        for n in -9..=9isize {
+            let expected = (0..n.rem_euclid(4)).fold(v, |v, _| right(v));
+            let turned = v.rotate_quarter_turns(n).unwrap();
+            assert_eq!(turned, expected, "{:?} turned {} times", v, n);
+            // turning back undoes it, and the length never changes
+            assert_eq!(turned.rotate_quarter_turns(-n), Some(v));
+            assert_eq!(turned.manhattan(), v.manhattan());
+        }
+        // left is the opposite of right
+        assert_eq!(v.rotate_quarter_turns(-1), v.rotate_quarter_turns(3));
+        assert_eq!(v.rotate_quarter_turns(-2), v.rotate_quarter_turns(2));
+    }
+}
+
+#[test]
+fn test_scale() {
+    let v = Vec2 { x: 3, y: -2 };
+    assert_eq!(v.scale(0), Some(Vec2 { x: 0, y: 0 }));
+    assert_eq!(v.scale(4), Some(Vec2 { x: 12, y: -8 }));
+    assert_eq!(v.scale(-1), Some(Vec2 { x: -3, y: 2 }));
+    assert_eq!(v.scale(i64::MAX), None);
+}
+
+#[test]
+fn test_angle_delta() {
+    for (angle, normalized) in [
+        (-5, 3),
+        (-4, 0),
+        (-3, 1),
+        (-2, 2),
+        (-1, 3),
+        (0, 0),
+        (1, 1),
+        (3, 3),
+        (4, 0),
+        (9, 1),
+    ] {
+        assert_eq!(AngleDelta(angle).normalized(), AngleDelta(normalized));
+    }
+    assert_eq!(AngleDelta(3) + AngleDelta(2), AngleDelta(5));
+    assert_eq!((AngleDelta(3) + AngleDelta(2)).normalized(), AngleDelta(1));
+    assert_eq!((AngleDelta(1) + AngleDelta(-3)).normalized(), AngleDelta(2));
+}
+
+#[test]
+fn test_overflow() {
+    // enough to overflow a 32-bit isize, but nowhere near an i64
+    let instructions = parse_instructions(&"F2147483647\n".repeat(4)).unwrap();
+    assert_eq!(part1(&instructions), Ok(4 * 2147483647));
+    assert_eq!(part2(&instructions), Ok(11 * 4 * 2147483647));
+
+    // the second instruction takes the ship past i64::MAX
+    let instructions = parse_instructions("F9223372036854775807\nE1\nN1").unwrap();
+    assert_eq!(
+        part1(&instructions),
+        Err(NavigationError::Overflow(Instruction::Move(Direction::East, 1)))
+    );
+    let path: Vec<_> = states::<ShipState1>(&instructions).collect();
+    assert_eq!(path.len(), 3);
+    assert!(path[2].is_err());
+    // while the waypoint is 10 units east to begin with
+    assert_eq!(
+        part2(&instructions),
+        Err(NavigationError::Overflow(Instruction::Advance(i64::MAX)))
+    );
+
+    // turning can overflow too, there's one more negative i64 than there are positive ones
+    let state = WaypointState {
+        ship: Vec2 { x: 0, y: 0 },
+        waypoint: Vec2 { x: i64::MIN, y: 0 },
+    };
+    assert_eq!(
+        state + Instruction::Rotate(AngleDelta(1)),
+        Err(NavigationError::Overflow(Instruction::Rotate(AngleDelta(1))))
+    );
+    assert_eq!(
+        Vec2 {
+            x: i64::MIN,
+            y: i64::MIN
+        }
+        .manhattan(),
+        u64::MAX
+    );
+}
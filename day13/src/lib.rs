@@ -0,0 +1,1010 @@
+// in `day13/src/lib.rs`
+
+use itertools::Itertools;
+use log::{debug, trace};
+use std::fmt;
+
+#[derive(Debug)]
+pub struct ProblemStatement1 {
+    pub departure_time: usize,
+    pub buses: Vec<usize>,
+}
+
+impl ProblemStatement1 {
+    /// Same input as `ProblemStatement::parse()`, without the positions of the buses
+    pub fn parse(input: &str) -> Result<Self, ScheduleParseError> {
+        let stat = ProblemStatement::parse(input)?;
+        Ok(ProblemStatement1 {
+            departure_time: stat.departure_time,
+            buses: stat.buses.iter().map(|bus| bus.id).collect(),
+        })
+    }
+
+    // we need to find the bus that leaves at the earliest time following our earliest departure time
+    // i.e. the minimum WaitTime::wait
+    pub fn earliest_bus(&self) -> Option<WaitTime> {
+        self.buses
+            .iter()
+            .map(|&bus_id| WaitTime {
+                bus_id,
+                wait: bus_id - self.departure_time % bus_id,
+            })
+            .min_by_key(|wt| wt.wait)
+    }
+}
+
+#[derive(Debug)]
+pub struct ProblemStatement {
+    pub departure_time: usize,
+    pub buses: Vec<Bus>,
+}
+
+#[derive(Debug)]
+pub struct Bus {
+    pub id: usize,
+    pub time_offset: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ScheduleParseError {
+    /// The input stops before the given (1-based) line
+    MissingLine(usize),
+    /// The first line isn't a departure time
+    BadTimestamp(String),
+    /// The bus at that index is neither `x` nor a bus ID (non-zero, and at most `u32::MAX`)
+    BadBusEntry { index: usize, entry: String },
+}
+
+impl fmt::Display for ScheduleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleParseError::MissingLine(line) => write!(f, "missing line {}", line),
+            ScheduleParseError::BadTimestamp(text) => write!(f, "invalid departure time {:?}", text),
+            ScheduleParseError::BadBusEntry { index, entry } => {
+                write!(f, "invalid bus entry {:?} at index {}", entry, index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScheduleParseError {}
+
+impl ProblemStatement {
+    pub fn parse(input: &str) -> Result<Self, ScheduleParseError> {
+        let mut lines = input.lines();
+        let departure_time = lines.next().ok_or(ScheduleParseError::MissingLine(1))?;
+        let departure_time = departure_time
+            .parse()
+            .map_err(|_| ScheduleParseError::BadTimestamp(departure_time.to_string()))?;
+
+        let mut buses = Vec::new();
+        for (index, entry) in lines.next().ok_or(ScheduleParseError::MissingLine(2))?.split(',').enumerate() {
+            if entry == "x" {
+                continue;
+            }
+            // a bus with ID 0 would have us compute modulo 0, and congruences are modulo a u32
+            match entry.parse() {
+                Ok(id) if id > 0 && u32::try_from(id).is_ok() => buses.push(Bus { id, time_offset: index }),
+                _ => {
+                    return Err(ScheduleParseError::BadBusEntry {
+                        index,
+                        entry: entry.to_string(),
+                    })
+                }
+            }
+        }
+
+        Ok(ProblemStatement { departure_time, buses })
+    }
+
+    pub fn check_solution(&self, solution: usize) -> Result<(), WrongGap<'_>> {
+        self.buses
+            .iter()
+            .tuple_windows()
+            // 👇 here's our `try_fold` used to "short-circuit" a fold
+            .try_fold(solution, |acc, (earlier, later)| {
+                // (note that `acc` is a `usize`, not a `Result<usize, WrongGap>`)
+                let earlier_timestamp = acc;
+                let offset_gap = later.time_offset - earlier.time_offset;
+
+                // the later bus must depart exactly `offset_gap` minutes after the earlier one. That gap can be
+                // longer than the later bus' period, so compare it with the wait until its next departure
+                // modulo that period (no wait at all when both depart at the same time).
+                let actual_gap = (later.id - earlier_timestamp % later.id) % later.id;
+
+                // 👇 we still return a `Result` though!
+                if offset_gap % later.id == actual_gap {
+                    Ok(earlier_timestamp + offset_gap)
+                } else {
+                    Err(WrongGap {
+                        earlier,
+                        later,
+                        earlier_timestamp,
+                        offset_gap,
+                        actual_gap,
+                    })
+                }
+            })
+            .map(|_| ())
+    }
+
+    /*fn solve(&self) -> usize {
+        let first_bus = self.buses.first().unwrap();
+        itertools::iterate(0, |&i| i + first_bus.id)
+            .find(|&timestamp| self.check_solution(timestamp).is_ok())
+            .unwrap()
+    }
+    */
+    // congruences() has one `x ≡ -offset (mod id)` per bus, x being the timestamp of the first bus
+    fn congruences(&self) -> impl Iterator<Item = LinearCongruence> + '_ {
+        self.buses.iter().map(|bus| LinearCongruence {
+            lhs: Expr::Var('x'),
+            // 👇👇👇
+            rhs: Expr::Literal((bus.id as i64 - bus.time_offset as i64).rem_euclid(bus.id as _)),
+            //rhs: Expr::Literal(bus.time_offset as _),
+            // `parse()` only accepts IDs that fit
+            modulo: u32::try_from(bus.id).expect("bus IDs fit in a u32"),
+        })
+    }
+
+    // Schedules with bus IDs sharing a factor are rejected, even when their offsets happen to be compatible
+    // (e.g. `6,x,x,9`, solved by 6): the CRT as used here only handles pairwise coprime moduli.
+    pub fn solve(&self) -> Result<i64, CantSolve> {
+        validate_coprime(&self.buses).map_err(|(a, b, gcd)| CantSolve::NotCoprime { a, b, gcd })?;
+        solve_lincong_system_direct(self.congruences())
+    }
+
+    // solve_symbolic() gets to the same answer by rewriting expressions, one congruence at a time
+    pub fn solve_symbolic(&self) -> Result<i64, CantSolve> {
+        validate_coprime(&self.buses).map_err(|(a, b, gcd)| CantSolve::NotCoprime { a, b, gcd })?;
+        solve_lincon_system(self.congruences())
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Checks that no two bus IDs share a factor, returning the first two that do along with their gcd
+fn validate_coprime(buses: &[Bus]) -> Result<(), (usize, usize, u64)> {
+    buses.iter().tuple_combinations().try_for_each(|(a, b)| match gcd(a.id as u64, b.id as u64) {
+        1 => Ok(()),
+        gcd => Err((a.id, b.id, gcd)),
+    })
+}
+
+#[derive(Debug)]
+pub struct WaitTime {
+    pub bus_id: usize,
+    /// in minutes
+    pub wait: usize,
+}
+
+pub struct WrongGap<'a> {
+    pub earlier: &'a Bus,
+    pub later: &'a Bus,
+    pub earlier_timestamp: usize,
+    pub offset_gap: usize,
+    pub actual_gap: usize,
+}
+
+impl fmt::Debug for WrongGap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected Bus {} to leave {} minutes after Bus {}, but it left {} minutes after",
+            self.later.id, self.offset_gap, self.earlier.id, self.actual_gap
+        )
+    }
+}
+
+impl fmt::Display for WrongGap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl std::error::Error for WrongGap<'_> {}
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum Expr {
+    Literal(i64),
+    Var(char),
+    Add(Vec<Expr>),
+    Mul(Vec<Expr>),
+    /// `-expr`, which `reduce()` turns into `-1 * expr`: subtracting `b` from `a` is `a + -b`
+    Neg(Box<Expr>),
+}
+
+impl fmt::Debug for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            &Expr::Literal(lit) => write!(f, "{}", lit),
+            //  👇
+            Expr::Var(c) => write!(f, "{}", c),
+            Expr::Add(terms) => {
+                write!(f, "(")?;
+                for (i, term) in terms.iter().enumerate() {
+                    if i == 0 {
+                        write!(f, "{:?}", term)?;
+                    } else {
+                        write!(f, " + {:?}", term)?;
+                    }
+                }
+                write!(f, ")")?;
+                Ok(())
+            }
+            Expr::Mul(terms) => {
+                write!(f, "(")?;
+                for (i, term) in terms.iter().enumerate() {
+                    if i == 0 {
+                        write!(f, "{:?}", term)?;
+                    } else {
+                        write!(f, " * {:?}", term)?;
+                    }
+                }
+                write!(f, ")")?;
+                Ok(())
+            }
+            Expr::Neg(expr) => write!(f, "-{:?}", expr),
+        }
+    }
+}
+
+impl Expr {
+    /// Multiply `self` by `expr`
+    fn mul(&self, expr: Expr) -> Self {
+        match self {
+            Self::Mul(items) => {
+                Self::Mul(std::iter::once(expr).chain(items.iter().cloned()).collect())
+            }
+            _ => Self::Mul(vec![expr, self.clone()]),
+        }
+    }
+
+    /// Add `self` by `expr`
+    fn add(&self, expr: Expr) -> Self {
+        match self {
+            Self::Add(items) => {
+                Self::Add(std::iter::once(expr).chain(items.iter().cloned()).collect())
+            }
+            _ => Self::Add(vec![expr, self.clone()]),
+        }
+    }
+
+    /// Negate `self`
+    fn neg(&self) -> Self {
+        match self {
+            &Self::Literal(lit) => Self::Literal(-lit),
+            Self::Neg(expr) => (**expr).clone(),
+            _ => Self::Neg(Box::new(self.clone())),
+        }
+    }
+
+    fn modulo(&self, modulo: u32) -> Self {
+        match self {
+            &Self::Literal(lit) => Expr::Literal(lit.rem_euclid(modulo as _)),
+            Self::Var(c) => Expr::Var(*c),
+            Self::Add(_) => self.clone(),
+            Self::Mul(items) => Self::Mul(items.iter().map(|x| x.modulo(modulo)).collect()),
+            Self::Neg(expr) => Self::Neg(Box::new(expr.modulo(modulo))),
+        }
+    }
+
+    // Replaces `Expr::Var` with `expr` everywhere in that expression
+    fn replace(&self, expr: Expr) -> Self {
+        match self {
+            &Expr::Literal(lit) => Expr::Literal(lit),
+            Expr::Var(_) => expr,
+            Expr::Add(items) => Expr::Add(
+                items
+                    .iter()
+                    .map(|ex| ex.replace(expr.clone()))
+                    .collect(),
+            ),
+            Expr::Mul(items) => Expr::Mul(
+                items
+                    .iter()
+                    .map(|ex| ex.replace(expr.clone()))
+                    .collect(),
+            ),
+            Expr::Neg(ex) => Expr::Neg(Box::new(ex.replace(expr))),
+        }
+    }
+
+    /// Expands products of sums, wherever they are and whatever the number of factors,
+    /// e.g. `(x * (1 + y) * 3)` gives `((x * 1 * 3) + (x * y * 3))`
+    fn distribute(&self) -> Self {
+        match self {
+            Self::Mul(items) => {
+                let items: Vec<_> = items.iter().map(Self::distribute).collect();
+                match items.iter().position(|item| matches!(item, Self::Add(_))) {
+                    // every term of the sum gets multiplied by all the other factors
+                    Some(index) => match &items[index] {
+                        Self::Add(add_terms) => Self::Add(
+                            add_terms
+                                .iter()
+                                .map(|term| {
+                                    let mut factors = items.clone();
+                                    factors[index] = term.clone();
+                                    Self::Mul(factors).distribute()
+                                })
+                                .collect(),
+                        ),
+                        _ => unreachable!(),
+                    },
+                    None => Self::Mul(items),
+                }
+            }
+            Self::Add(items) => Self::Add(items.iter().map(Self::distribute).collect()),
+            Self::Neg(expr) => Self::Mul(vec![Self::Literal(-1), (**expr).clone()]).distribute(),
+            Self::Literal(_) | Self::Var(_) => self.clone(),
+        }
+    }
+
+    fn reduce(&self) -> Expr {
+        match self {
+            &Expr::Literal(lit) => Expr::Literal(lit),
+            Expr::Var(c) => Expr::Var(*c),
+            Expr::Neg(expr) => Expr::Mul(vec![Expr::Literal(-1), (**expr).clone()]).reduce(),
+            Expr::Add(items) => {
+                // 👇 new!
+                if let Some((index, nested_items)) =
+                items
+                    .iter()
+                    .enumerate()
+                    .find_map(|(index, item)| match item {
+                        Expr::Add(terms) => Some((index, terms)),
+                        _ => None,
+                    })
+                {
+                    return Expr::Add(
+                        items
+                            .iter()
+                            .enumerate()
+                            .filter(|&(i, _)| i != index)
+                            .map(|(_, item)| item)
+                            .chain(nested_items)
+                            .cloned()
+                            .collect(),
+                    )
+                        .reduce();
+                }
+                let (literals, others): (Vec<_>, Vec<_>) = items
+                    .iter()
+                    .map(Self::reduce)
+                    .partition(|x| matches!(x, Self::Literal(_)));
+
+                if literals.is_empty() && others.is_empty() {
+                    Expr::Literal(0)
+                } else {
+                    let mut terms = others;
+                    let sum = literals
+                        .into_iter()
+                        .map(|x| {
+                            if let Expr::Literal(x) = x {
+                                x
+                            } else {
+                                unreachable!()
+                            }
+                        })
+                        .sum();
+                    // 👇 even a sum of 0 is all that's left when there are no other terms
+                    if terms.is_empty() {
+                        return Self::Literal(sum);
+                    } else if sum != 0 {
+                        terms.insert(0, Self::Literal(sum));
+                    }
+                    if terms.len() == 1 {
+                        terms.pop().unwrap()
+                    } else {
+                        Expr::Add(terms)
+                    }
+                }
+            }
+            Expr::Mul(items) => {
+                // nested products get flattened, just like nested sums
+                if let Some((index, nested_items)) =
+                items
+                    .iter()
+                    .enumerate()
+                    .find_map(|(index, item)| match item {
+                        Expr::Mul(factors) => Some((index, factors)),
+                        _ => None,
+                    })
+                {
+                    return Expr::Mul(
+                        items
+                            .iter()
+                            .enumerate()
+                            .filter(|&(i, _)| i != index)
+                            .map(|(_, item)| item)
+                            .chain(nested_items)
+                            .cloned()
+                            .collect(),
+                    )
+                        .reduce();
+                }
+                let (literals, others): (Vec<_>, Vec<_>) = items
+                    .iter()
+                    .map(Self::reduce)
+                    .partition(|x| matches!(x, Self::Literal(_)));
+
+                if literals.is_empty() && others.is_empty() {
+                    Expr::Literal(1)
+                } else {
+                    let mut terms = others;
+                    let product = literals
+                        .into_iter()
+                        .map(|x| {
+                            if let Expr::Literal(x) = x {
+                                x
+                            } else {
+                                unreachable!()
+                            }
+                        })
+                        .product();
+                    // 👇 even a product of 1 is all that's left when there are no other terms
+                    if terms.is_empty() {
+                        return Self::Literal(product);
+                    } else if product != 1 {
+                        terms.insert(0, Self::Literal(product));
+                    }
+                    if terms.len() == 1 {
+                        terms.pop().unwrap()
+                    } else {
+                        Expr::Mul(terms)
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct LinearCongruence {
+    lhs: Expr,
+    rhs: Expr,
+    modulo: u32,
+}
+
+impl fmt::Debug for LinearCongruence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ≡ {:?} (mod {})", self.lhs, self.rhs, self.modulo)
+    }
+}
+
+#[derive(Debug)]
+pub enum CantSolve {
+    /// The congruence isn't of a form we know how to solve
+    Unsupported(LinearCongruence),
+    /// `factor * var` can't be turned into `var`: `factor` has no inverse because it's not coprime with the modulus
+    NoInverse { congruence: LinearCongruence, factor: i64 },
+    /// The solution, or the numbers needed to get to it, are too large
+    Overflow,
+    /// There are no congruences to solve, e.g. every bus is `x`
+    Empty,
+    /// Bus IDs `a` and `b` aren't coprime, they have `gcd` in common
+    NotCoprime { a: usize, b: usize, gcd: u64 },
+    /// Expected a solved congruence, of the form `var ≡ literal (mod m)`
+    Unsolved(LinearCongruence),
+    /// Expected the expression to reduce to a literal once every variable is 0
+    NotALiteral(Expr),
+}
+
+impl fmt::Display for CantSolve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CantSolve::Unsupported(congruence) => write!(f, "don't know how to solve {:?}", congruence),
+            CantSolve::NoInverse { congruence, factor } => write!(
+                f,
+                "can't solve {:?}: {} has no inverse modulo {}",
+                congruence, factor, congruence.modulo
+            ),
+            CantSolve::Overflow => write!(f, "the solution doesn't fit in an i64"),
+            CantSolve::Empty => write!(f, "there are no congruences to solve"),
+            CantSolve::NotCoprime { a, b, gcd } => {
+                write!(f, "bus IDs {} and {} aren't coprime (gcd {})", a, b, gcd)
+            }
+            CantSolve::Unsolved(congruence) => write!(f, "expected a solved congruence, got {:?}", congruence),
+            CantSolve::NotALiteral(expr) => write!(f, "expected a literal, got {:?}", expr),
+        }
+    }
+}
+
+impl std::error::Error for CantSolve {}
+
+impl LinearCongruence {
+    /// Multiply both sides of congruence by `expr`
+    fn mul(&self, expr: Expr) -> Self {
+        Self {
+            lhs: self.lhs.mul(expr.clone()).reduce().modulo(self.modulo),
+            rhs: self.rhs.mul(expr).reduce().modulo(self.modulo),
+            modulo: self.modulo,
+        }
+    }
+
+    /// Add both sides of congruence by `expr`
+    fn add(&self, expr: Expr) -> Self {
+        Self {
+            lhs: self.lhs.add(expr.clone()).reduce().modulo(self.modulo),
+            rhs: self.rhs.add(expr).reduce().modulo(self.modulo),
+            modulo: self.modulo,
+        }
+    }
+
+    /// Subtract `expr` from both sides of congruence
+    fn sub(&self, expr: Expr) -> Self {
+        self.add(expr.neg())
+    }
+
+    /// Distributes and reduces both sides, so `solve()` only has a few shapes to deal with
+    fn normalize(&self) -> Self {
+        Self {
+            lhs: self.lhs.distribute().reduce().modulo(self.modulo),
+            rhs: self.rhs.distribute().reduce().modulo(self.modulo),
+            modulo: self.modulo,
+        }
+    }
+
+    fn solve(&self) -> Result<Self, CantSolve> {
+        trace!("should solve {:?}", self);
+        let normalized = self.normalize();
+        if normalized != *self {
+            return normalized.solve();
+        }
+
+        if let Expr::Mul(items) = &self.lhs {
+            if let [Expr::Literal(lit), Expr::Var(_)] = items[..] {
+                let mmi = modular_multiplicative_inverse(lit, self.modulo).ok_or_else(|| CantSolve::NoInverse {
+                    congruence: self.clone(),
+                    factor: lit,
+                })?;
+                trace!("multiplying by mmi: {}", mmi);
+                return self.mul(Expr::Literal(mmi)).solve();
+            }
+        }
+
+        if let Expr::Add(items) = &self.lhs {
+            if let Some(lit) = items.iter().find_map(|expr| match *expr {
+                Expr::Literal(lit) => Some(lit),
+                _ => None,
+            }) {
+                trace!("subtracting {} on both sides", lit);
+                return self.sub(Expr::Literal(lit)).solve();
+            }
+        }
+
+        if let Expr::Var(_) = &self.lhs {
+            // already solved!
+            return Ok(self.clone());
+        }
+
+        Err(CantSolve::Unsupported(self.clone()))
+    }
+
+    /// Turns this linear congruence into an expression,
+    /// for example `x ≡ 7 (mod 13)` would give `13*var + 7`.
+    /// Fails if linear congruence is not solved yet.
+    //               👇
+    fn expr(&self, name: char) -> Result<Expr, CantSolve> {
+        match (&self.lhs, &self.rhs) {
+            (Expr::Var(_), &Expr::Literal(remainder)) => Ok(Expr::Add(vec![
+                //                                                         👇
+                Expr::Mul(vec![Expr::Literal(self.modulo as _), Expr::Var(name)]),
+                Expr::Literal(remainder),
+            ])),
+            _ => Err(CantSolve::Unsolved(self.clone())),
+        }
+    }
+
+    // Replaces `Expr::Var` with `expr` everywhere in that expression
+    fn replace(&self, expr: Expr) -> Self {
+        Self {
+            lhs: self.lhs.replace(expr.clone()),
+            rhs: self.rhs.replace(expr),
+            modulo: self.modulo,
+        }
+    }
+}
+
+/// Finds the modular multiplicative inverse of `a` modulo `m`, with the extended Euclidean algorithm.
+/// There is one only if `a` and `m` are coprime, otherwise this returns None.
+fn modular_multiplicative_inverse(a: i64, m: u32) -> Option<i64> {
+    let m = m as i64;
+    // the remainders go down just like in Euclid's algorithm, and all along `s * a ≡ r (mod m)`
+    let (mut old_r, mut r) = (a.rem_euclid(m), m);
+    let (mut old_s, mut s) = (1, 0);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_s, s) = (s, old_s - quotient * s);
+    }
+    // old_r is now gcd(a, m)
+    (old_r == 1).then(|| old_s.rem_euclid(m))
+}
+
+fn solve_lincon_system<I>(mut cons: I) -> Result<i64, CantSolve>
+    where
+        I: Iterator<Item = LinearCongruence> {
+    // Variable naming
+    let mut curr_var = b'a';
+    let mut next_var = || -> char {
+        let res = curr_var as char;
+        curr_var += 1;
+        res
+    };
+
+    //let mut cons = cons.iter(); // now part of function signature
+    let con = cons.next().ok_or(CantSolve::Empty)?;
+    debug!("👉 {:?}", con);
+    let mut x = con.solve()?.expr(next_var())?.reduce();
+    debug!("x = {:?}", x);
+
+    for con in cons {
+        debug!("👉 {:?}", con);
+        x = x
+            .replace(con.replace(x.clone()).solve()?.expr(next_var())?)
+            .distribute()
+            .reduce();
+        debug!("x = {:?}", x);
+    }
+
+    let x = x.replace(Expr::Literal(0)).reduce();
+    if let Expr::Literal(lit) = x {
+        Ok(lit)
+    } else {
+        Err(CantSolve::NotALiteral(x))
+    }
+}
+
+/// Solves a system of `x ≡ a_i (mod m_i)` congruences with the Chinese remainder theorem: with N the product of all
+/// moduli and N_i = N / m_i, x is the sum of every a_i * N_i * M_i modulo N, M_i being the inverse of N_i modulo m_i.
+#[allow(non_snake_case)]
+fn solve_lincong_system_direct<I>(congs: I) -> Result<i64, CantSolve>
+    where
+        I: Iterator<Item = LinearCongruence>,
+{
+    // This time, we need to be able to index our linear congruences
+    let congs: Vec<_> = congs.collect();
+    if congs.is_empty() {
+        return Err(CantSolve::Empty);
+    }
+
+    fn remainder(lc: &LinearCongruence) -> Result<i64, CantSolve> {
+        match (&lc.lhs, &lc.rhs) {
+            (Expr::Var(_), Expr::Literal(lit)) => Ok(*lit),
+            _ => Err(CantSolve::Unsolved(lc.clone())),
+        }
+    }
+
+    // The terms get huge quickly (N_i is the product of every other modulus), so this works in i128 and reduces
+    // modulo N, the product of all moduli, after every multiplication. Any two factors are then below N, which
+    // keeps their product in range for as long as the answer could fit in an i64.
+    let N = congs
+        .iter()
+        .try_fold(1i128, |N, con| N.checked_mul(con.modulo as i128))
+        .ok_or(CantSolve::Overflow)?;
+    let mul_mod = |a: i128, b: i128| a.checked_mul(b).map(|ab| ab % N).ok_or(CantSolve::Overflow);
+
+    let mut x = 0;
+    for con in &congs {
+        let m_i = con.modulo as i128;
+        let a_i = (remainder(con)? as i128).rem_euclid(m_i);
+        let N_i = N / m_i;
+
+        // N_i % m_i is below m_i, so it fits in an i64
+        let factor = (N_i % m_i) as i64;
+        let M_i = modular_multiplicative_inverse(factor, con.modulo).ok_or_else(|| CantSolve::NoInverse {
+            congruence: con.clone(),
+            factor,
+        })?;
+
+        x = (x + mul_mod(mul_mod(a_i, N_i)?, M_i as i128)?) % N;
+    }
+    i64::try_from(x).map_err(|_| CantSolve::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solutions() {
+        // using a macro to allow us to group all the test's data neatly in one place,
+        // and leave the logic elsewhere
+        macro_rules! test {
+            ($list: literal, $solution: expr) => {
+                let stat = ProblemStatement::parse(concat!("0\n", $list, "\n")).unwrap();
+                assert_eq!(stat.solve().unwrap(), $solution);
+                assert!(stat.check_solution($solution).is_ok());
+            };
+        }
+
+        test!("17,x,13,19", 3417);
+        test!("67,7,59,61", 754018);
+        test!("67,x,7,59,61", 779210);
+        test!("67,7,x,59,61", 1261476);
+        test!("1789,37,47,1889", 1202161486);
+    }
+
+    #[test]
+    fn test_reduce() {
+        assert_eq!(Expr::Add(vec![]).reduce(), Expr::Literal(0).reduce());
+
+        assert_eq!(
+            Expr::Add(vec![Expr::Literal(2), Expr::Literal(3)]).reduce(),
+            Expr::Add(vec![Expr::Literal(5)]).reduce(),
+        );
+
+        assert_eq!(
+            Expr::Add(vec![Expr::Literal(2), Expr::Literal(3), Expr::Literal(5)]).reduce(),
+            Expr::Add(vec![Expr::Literal(10)]).reduce(),
+        );
+
+        assert_eq!(
+            Expr::Add(vec![Expr::Literal(2), Expr::Literal(3), Expr::Var('x')]).reduce(),
+            Expr::Add(vec![Expr::Literal(5), Expr::Var('x')]).reduce(),
+        );
+
+        assert_eq!(
+            Expr::Mul(vec![Expr::Literal(2), Expr::Literal(3), Expr::Var('x')]).reduce(),
+            Expr::Mul(vec![Expr::Literal(6), Expr::Var('x')]).reduce(),
+        );
+
+        assert_eq!(
+            Expr::Mul(vec![
+                Expr::Add(vec![Expr::Literal(2), Expr::Literal(3)]),
+                Expr::Literal(10),
+                Expr::Var('x')
+            ])
+                .reduce(),
+            Expr::Mul(vec![Expr::Literal(50), Expr::Var('x')]).reduce(),
+        );
+    }
+
+    #[test]
+    fn test_modular_multiplicative_inverse() {
+        // composite moduli, for which Fermat's little theorem doesn't hold
+        let cases = [(3, 4, Some(3)), (2, 9, Some(5)), (4, 9, Some(7)), (7, 15, Some(13)), (-2, 15, Some(7))];
+        for (a, m, inverse) in cases {
+            assert_eq!(modular_multiplicative_inverse(a, m), inverse, "{} mod {}", a, m);
+        }
+        // no inverse without a gcd of 1
+        assert_eq!(modular_multiplicative_inverse(2, 4), None);
+        assert_eq!(modular_multiplicative_inverse(6, 9), None);
+        assert_eq!(modular_multiplicative_inverse(5, 15), None);
+        // and still right for primes
+        for a in 1..13 {
+            let inverse = modular_multiplicative_inverse(a, 13).unwrap();
+            assert_eq!(a * inverse % 13, 1);
+        }
+    }
+
+    #[test]
+    fn test_composite_modulus() {
+        // x ≡ 0 (mod 4) and x ≡ 8 (mod 9): the inverse of 4 modulo 9 is 7, Fermat gave 4^7 mod 9 = 4 instead
+        assert_eq!(ProblemStatement::parse("0\n4,9").unwrap().solve_symbolic().unwrap(), 8);
+        // 6 has no inverse modulo 9
+        let stat = ProblemStatement::parse("0\n6,9").unwrap();
+        assert!(matches!(
+            solve_lincon_system(stat.congruences()),
+            Err(CantSolve::NoInverse { factor: 6, .. })
+        ));
+        // same answers with the direct solver, which needs the inverse of 9 modulo 4 and of 4 modulo 9
+        assert_eq!(ProblemStatement::parse("0\n4,9").unwrap().solve().unwrap(), 8);
+        assert!(matches!(
+            solve_lincong_system_direct(stat.congruences()),
+            Err(CantSolve::NoInverse { factor: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_direct_large_moduli() {
+        let congruence = |remainder, modulo| LinearCongruence {
+            lhs: Expr::Var('x'),
+            rhs: Expr::Literal(remainder),
+            modulo,
+        };
+
+        // a_i * N_i * M_i is around 10^27 here, far beyond an i64
+        let (p, q) = (1_000_000_007, 1_000_000_009);
+        let x = solve_lincong_system_direct(vec![congruence(5, p), congruence(7, q)].into_iter()).unwrap();
+        assert!((0..p as i64 * q as i64).contains(&x));
+        assert_eq!((x % p as i64, x % q as i64), (5, 7));
+
+        // a third modulus of the same size and the solution itself can't fit anymore
+        let congs = vec![congruence(5, p), congruence(7, q), congruence(11, 999_999_937)];
+        assert!(matches!(solve_lincong_system_direct(congs.into_iter()), Err(CantSolve::Overflow)));
+
+        // it agrees with the symbolic solver on smaller inputs
+        for list in ["0\n17,x,13,19", "0\n67,7,59,61", "0\n1789,37,47,1889", include_str!("input.txt")] {
+            let stat = ProblemStatement::parse(list).unwrap();
+            assert_eq!(stat.solve().unwrap(), stat.solve_symbolic().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        use ScheduleParseError::*;
+
+        assert_eq!(ProblemStatement::parse("").unwrap_err(), MissingLine(1));
+        assert_eq!(ProblemStatement::parse("939\n").unwrap_err(), MissingLine(2));
+        assert_eq!(
+            ProblemStatement::parse("soon\n7,13").unwrap_err(),
+            BadTimestamp("soon".to_string())
+        );
+        assert_eq!(
+            ProblemStatement::parse("939\n17,,13").unwrap_err(),
+            BadBusEntry {
+                index: 1,
+                entry: "".to_string()
+            }
+        );
+        assert_eq!(
+            ProblemStatement::parse("939\n7,13,y,59").unwrap_err(),
+            BadBusEntry {
+                index: 2,
+                entry: "y".to_string()
+            }
+        );
+        assert_eq!(
+            ProblemStatement::parse("939\n7,0").unwrap_err(),
+            BadBusEntry {
+                index: 1,
+                entry: "0".to_string()
+            }
+        );
+        // too large to be a modulus, rather than truncated to one
+        assert_eq!(
+            ProblemStatement::parse("939\n7,4294967309").unwrap_err(),
+            BadBusEntry {
+                index: 1,
+                entry: "4294967309".to_string()
+            }
+        );
+        assert_eq!(ProblemStatement::parse("939\n7,4294967291").unwrap().buses[1].id, 4294967291);
+    }
+
+    #[test]
+    fn test_earliest_bus() {
+        let wt = ProblemStatement1::parse("939\n7,13,x,x,59,x,31,19").unwrap().earliest_bus().unwrap();
+        assert_eq!((wt.bus_id, wt.wait), (59, 5));
+
+        // same parsing errors as part 2, rather than panics
+        assert_eq!(
+            ProblemStatement1::parse("939\n7,0").unwrap_err(),
+            ScheduleParseError::BadBusEntry {
+                index: 1,
+                entry: "0".to_string()
+            }
+        );
+        assert_eq!(ProblemStatement1::parse("").unwrap_err(), ScheduleParseError::MissingLine(1));
+        assert!(ProblemStatement1::parse("939\nx,x").unwrap().earliest_bus().is_none());
+    }
+
+    #[test]
+    fn test_no_buses() {
+        let stat = ProblemStatement::parse("939\nx,x,x").unwrap();
+        assert!(stat.buses.is_empty());
+        assert!(matches!(stat.solve(), Err(CantSolve::Empty)));
+        assert!(matches!(stat.solve_symbolic(), Err(CantSolve::Empty)));
+    }
+
+    #[test]
+    fn test_validate_coprime() {
+        let stat = ProblemStatement::parse("0\n17,x,13,19").unwrap();
+        assert_eq!(validate_coprime(&stat.buses), Ok(()));
+        let stat = ProblemStatement::parse("0\n4,9,x,10").unwrap();
+        assert_eq!(validate_coprime(&stat.buses), Err((4, 10, 2)));
+
+        // x ≡ 0 (mod 6) and x ≡ 8 (mod 9) can't both hold, x would be both 0 and 2 modulo 3
+        let stat = ProblemStatement::parse("0\n6,9").unwrap();
+        assert!(matches!(stat.solve(), Err(CantSolve::NotCoprime { a: 6, b: 9, gcd: 3 })));
+        assert!(matches!(stat.solve_symbolic(), Err(CantSolve::NotCoprime { a: 6, b: 9, gcd: 3 })));
+
+        // x ≡ 0 (mod 6) and x ≡ 6 (mod 9) are compatible (x = 6), but rejected all the same
+        let stat = ProblemStatement::parse("0\n6,x,x,9").unwrap();
+        assert!(matches!(stat.solve(), Err(CantSolve::NotCoprime { a: 6, b: 9, gcd: 3 })));
+    }
+
+    #[test]
+    fn test_check_solution() {
+        let stat = ProblemStatement::parse("939\n7,13,x,x,59,x,31,19").unwrap();
+        assert!(stat.check_solution(1068781).is_ok());
+        let err = stat.check_solution(1068782).unwrap_err();
+        assert_eq!((err.earlier.id, err.later.id, err.offset_gap, err.actual_gap), (7, 13, 1, 0));
+        assert_eq!(
+            err.to_string(),
+            "expected Bus 13 to leave 1 minutes after Bus 7, but it left 0 minutes after"
+        );
+
+        // 0 and 15 are multiples of 3, and bus 3 has to leave 6 minutes (two whole periods) after bus 5: no wait is
+        // right, rounding up to the next multiple used to make that a 3 minute gap
+        let stat = ProblemStatement::parse("0\n5,x,x,x,x,x,3").unwrap();
+        assert_eq!(stat.solve().unwrap(), 0);
+        assert!(stat.check_solution(0).is_ok());
+        assert!(stat.check_solution(15).is_ok());
+        assert!(stat.check_solution(10).is_err());
+
+        let stat = ProblemStatement::parse(include_str!("input.txt")).unwrap();
+        assert!(stat.check_solution(stat.solve().unwrap() as usize).is_ok());
+    }
+
+    #[test]
+    fn test_distribute() {
+        use Expr::*;
+        let x = || Var('x');
+
+        // the sum used to be distributed only as the second of exactly two factors
+        assert_eq!(
+            Mul(vec![Add(vec![Literal(1), x()]), Literal(3)]).distribute().reduce(),
+            Add(vec![Literal(3), Mul(vec![Literal(3), x()])])
+        );
+        assert_eq!(
+            Mul(vec![Literal(2), Add(vec![Literal(1), x()]), Literal(3)]).distribute().reduce(),
+            Add(vec![Literal(6), Mul(vec![Literal(6), x()])])
+        );
+        assert_eq!(
+            Mul(vec![Add(vec![Literal(1), x()]), Add(vec![Literal(2), x()])]).distribute().reduce(),
+            Add(vec![Literal(2), x(), Mul(vec![Literal(2), x()]), Mul(vec![x(), x()])])
+        );
+        // and nested inside other expressions
+        assert_eq!(
+            Add(vec![Literal(1), Mul(vec![Mul(vec![Add(vec![x(), Literal(4)]), Literal(2)]), Literal(5)])])
+                .distribute()
+                .reduce(),
+            Add(vec![Literal(41), Mul(vec![Literal(10), x()])])
+        );
+    }
+
+    #[test]
+    fn test_negation() {
+        use Expr::*;
+        let x = || Var('x');
+
+        let expr = Neg(Box::new(Add(vec![Literal(3), x()])));
+        assert_eq!(format!("{:?}", expr), "-(3 + x)");
+        assert_eq!(expr.distribute().reduce(), Add(vec![Literal(-3), Mul(vec![Literal(-1), x()])]));
+        assert_eq!(expr.neg(), Add(vec![Literal(3), x()]));
+        assert_eq!(Literal(7).neg(), Literal(-7));
+
+        // 10 - 2 * (1 + x)
+        let expr = Add(vec![Literal(10), Neg(Box::new(Mul(vec![Literal(2), Add(vec![Literal(1), x()])])))]);
+        assert_eq!(expr.distribute().reduce(), Add(vec![Literal(8), Mul(vec![Literal(-2), x()])]));
+        assert_eq!(expr.replace(Literal(4)).reduce(), Literal(0));
+        assert_eq!(Mul(vec![Literal(-1), Literal(-1)]).reduce(), Literal(1));
+    }
+
+    #[test]
+    fn test_lincon_system_normalizes() {
+        use Expr::*;
+        let congruence = |lhs, rhs, modulo| LinearCongruence {
+            lhs,
+            rhs: Literal(rhs),
+            modulo,
+        };
+
+        // 2x ≡ 1 (mod 5) and x * 3 ≡ 2 (mod 7): this used to panic, expecting `x ≡ literal` for the first one
+        let congs = vec![
+            congruence(Mul(vec![Literal(2), Var('x')]), 1, 5),
+            congruence(Mul(vec![Var('x'), Literal(3)]), 2, 7),
+        ];
+        assert_eq!(solve_lincon_system(congs.into_iter()).unwrap(), 3);
+
+        // x - 4 ≡ 2 (mod 11) and -x ≡ 1 (mod 3)
+        let congs = vec![
+            congruence(Add(vec![Var('x'), Neg(Box::new(Literal(4)))]), 2, 11),
+            congruence(Neg(Box::new(Var('x'))), 1, 3),
+        ];
+        assert_eq!(solve_lincon_system(congs.into_iter()).unwrap(), 17);
+
+        // the direct solver only takes solved congruences, and says so instead of panicking
+        let congs = vec![congruence(Mul(vec![Literal(2), Var('x')]), 1, 5)];
+        assert!(matches!(solve_lincong_system_direct(congs.into_iter()), Err(CantSolve::Unsolved(_))));
+    }
+}
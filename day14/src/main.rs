@@ -1,28 +1,58 @@
+use anyhow::Context;
 use std::fmt;
 use std::collections::HashMap;
 use itertools::Itertools;
 
+/// Width of the machine's words, addresses and values alike (and so of masks too)
+const WORD_BITS: u32 = 36;
+
+#[derive(Debug, PartialEq)]
 struct Program {
     instructions: Vec<Instruction>,
 }
 
+#[derive(thiserror::Error, Debug, PartialEq)]
+#[error("line {line}: {text:?}, expected {expected}")]
+struct ProgramParseError {
+    line: usize,
+    text: String,
+    expected: String,
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for ins in &self.instructions {
+            writeln!(f, "{}", ins)?;
+        }
+        Ok(())
+    }
+}
+
 impl Program {
-    fn parse(input: &str) -> Self {
+    fn parse(input: &str) -> Result<Self, ProgramParseError> {
+        Self::parse_with_width(input, WORD_BITS)
+    }
+
+    /// Parses a program for a machine with `width`-bit words (up to 64)
+    fn parse_with_width(input: &str, width: u32) -> Result<Self, ProgramParseError> {
         peg::parser! {
             pub(crate) grammar parser() for str {
-                pub(crate) rule root(p: &mut Program)
-                    = (line(p) whitespace()*)* ![_]
+                pub(crate) rule root(p: &mut Program, width: u32)
+                    = (line(p, width) whitespace()*)* ![_]
 
-                rule line(p: &mut Program)
-                    = i:instruction() { p.instructions.push(i) }
+                rule line(p: &mut Program, width: u32)
+                    = i:instruction(width) { p.instructions.push(i) }
 
-                rule instruction() -> Instruction
-                    = set_mask()
-                    / assign()
+                rule instruction(width: u32) -> Instruction
+                    = set_mask(width)
+                    / assign(width)
 
-                rule set_mask() -> Instruction
-                    = "mask = " e:$(['X' | '0' | '1']+) {
-                        let mut mask: Mask = Default::default();
+                rule set_mask(width: u32) -> Instruction
+                    = "mask = " e:$(['X' | '0' | '1']+) {?
+                        if e.len() != width as usize {
+                            return Err("a mask with one character per bit of a word");
+                        }
+                        let mut mask = Mask::new(width);
                         for (i, x) in e.as_bytes().iter().rev().enumerate() {
                             match x {
                                 b'1' => mask.set |= 2_u64.pow(i as _),
@@ -30,14 +60,24 @@ impl Program {
                                 _ => {},
                             }
                         }
-                        Instruction::SetMask(mask)
+                        Ok(Instruction::SetMask(mask))
                     }
 
-                rule assign() -> Instruction
-                    = "mem[" addr:number() "] = " val:number() { Instruction::Assign { addr, val } }
+                rule assign(width: u32) -> Instruction
+                    = "mem[" addr:word(width) "] = " val:word(width) { Instruction::Assign { addr, val } }
+
+                // both addresses and values must fit in a word
+                rule word(width: u32) -> u64
+                    = n:number() {?
+                        if n.checked_shr(width).unwrap_or(0) == 0 {
+                            Ok(n)
+                        } else {
+                            Err("a number that fits in a word")
+                        }
+                    }
 
                 rule number() -> u64
-                    = e:$(['0'..='9']+) { e.parse().unwrap() }
+                    = e:$(['0'..='9']+) {? e.parse().or(Err("a number that fits in 64 bits")) }
 
                 rule whitespace()
                     = [' ' | '\t' | '\r' | '\n']
@@ -48,11 +88,16 @@ impl Program {
             instructions: Default::default(),
         };
 
-        parser::root(input, &mut program).unwrap();
-        program
+        parser::root(input, &mut program, width).map_err(|e| ProgramParseError {
+            line: e.location.line,
+            text: input.lines().nth(e.location.line - 1).unwrap_or_default().to_string(),
+            expected: e.expected.to_string(),
+        })?;
+        Ok(program)
     }
 }
 
+#[derive(PartialEq)]
 enum Instruction {
     SetMask(Mask),
     Assign { addr: u64, val: u64 },
@@ -71,17 +116,43 @@ impl fmt::Debug for Instruction {
     }
 }
 
-#[derive(Clone, Copy, Default)]
+// Same as the input: `mask = 000000000000000000000000000000X1001X` or `mem[42] = 100`
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::SetMask(mask) => write!(f, "mask = {}", mask),
+            Instruction::Assign { addr, val } => write!(f, "mem[{}] = {}", addr, val),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
 struct Mask {
     set: u64,
     clear: u64,
+    /// Number of bits, any bit above is neither set nor cleared nor X
+    width: u32,
+}
+
+// All X
+impl Default for Mask {
+    fn default() -> Self {
+        Self::new(WORD_BITS)
+    }
 }
 
 impl fmt::Debug for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+// Every bit, most significant first, as in the input
+impl fmt::Display for Mask {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         //write!(f, "set {:036b}, clear {:036b}", self.set, self.clear)
-        for i in 0..36 {
-            let mask = i << (36 - i);
+        for i in (0..self.width()).rev() {
+            let mask = 1 << i;
             write!(
                 f,
                 "{}",
@@ -99,6 +170,15 @@ impl fmt::Debug for Mask {
 }
 
 impl Mask {
+    /// A `width`-bit mask, all X
+    fn new(width: u32) -> Self {
+        Self { set: 0, clear: 0, width }
+    }
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
     fn apply(&self, x: u64) -> u64 {
         (x | self.set) & (!self.clear)
     }
@@ -107,7 +187,7 @@ impl Mask {
         let mut res = *self;
         let set_or_clear = self.set | self.clear;
 
-        for i in 0..36 {
+        for i in 0..self.width() {
             let mask = 1 << i;
             if set_or_clear & mask == 0 {
                 // mask has X, it stays X.
@@ -124,97 +204,415 @@ impl Mask {
     }
 
     fn x_positions(&self) -> impl Iterator<Item = u64> + '_ {
-        (0..36_u64).filter(move |i| ((1 << i) & (self.set | self.clear)) == 0)
+        (0..self.width() as u64).filter(move |i| ((1 << i) & (self.set | self.clear)) == 0)
     }
 
-    fn apply_x(&self, positions: Vec<u64>) -> Self {
-        // `Mask` is `Copy`, this is fine
-        let mut res = *self;
+    /// Every value the X bits can take, the other bits being those of `set`
+    fn each_binary_value(&self) -> impl Iterator<Item = u64> {
+        let (fixed, floating) = (self.set, self.floating());
+        // `(x - floating) & floating` is the next subset of the X bits after `x`: the subtraction adds one to the
+        // X bits (the bits in between are 0 in `x` and 1 in `-floating`, so the carry goes right through them),
+        // and the other bits are cleared. After `floating` itself comes 0 again, which is where we stop.
+        let mut next = Some(0_u64);
+        std::iter::from_fn(move || {
+            let x = next?;
+            next = Some(x.wrapping_sub(floating) & floating).filter(|&x| x != 0);
+            Some(fixed | x)
+        })
+    }
 
-        for pos in self.x_positions() {
-            let mask = 1_u64 << pos;
+    /// The X bits, as a mask
+    fn floating(&self) -> u64 {
+        self.x_positions().fold(0, |acc, pos| acc | 1 << pos)
+    }
 
-            // In practice, `positions` is short, so it's not
-            // worth constructing a `HashSet`
-            if positions.contains(&pos) {
-                // it's set, then!
-                res.set |= mask;
+    /// Number of addresses this mask stands for, once applied with `or()` (2^64 for 64 X bits)
+    fn address_count(&self) -> u128 {
+        1 << self.x_positions().count()
+    }
+
+    /// Whether two masks (applied with `or()`) have any address in common
+    fn overlaps(&self, other: &Self) -> bool {
+        self.set & other.clear == 0 && self.clear & other.set == 0
+    }
+
+    /// Splits the addresses of `self` that aren't addresses of `other` into disjoint masks,
+    /// one per X bit of `self` that `other` has a 0 or a 1 for.
+    fn subtract(&self, other: &Self) -> Vec<Self> {
+        if !self.overlaps(other) {
+            return vec![*self];
+        }
+
+        let mut pieces = Vec::new();
+        let mut rest = *self;
+        for pos in self.x_positions() {
+            let bit = 1_u64 << pos;
+            if other.floating() & bit != 0 {
+                continue;
+            }
+            // the half that disagrees with `other` on that bit is ours to keep,
+            // the half that agrees carries on to the next bit
+            let (mut piece, agreeing) = (rest, &mut rest);
+            if other.set & bit != 0 {
+                piece.clear |= bit;
+                agreeing.set |= bit;
             } else {
-                // it not set, it's cleared
-                res.clear |= mask;
+                piece.set |= bit;
+                agreeing.clear |= bit;
             }
+            pieces.push(piece);
         }
+        // whatever is left is entirely covered by `other`
+        pieces
+    }
+}
 
-        res
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DecoderVersion {
+    /// Part 1: the mask applies to values
+    V1,
+    /// Part 2: the mask applies to addresses, X bits floating
+    V2,
+}
+
+impl DecoderVersion {
+    fn part(self) -> usize {
+        match self {
+            DecoderVersion::V1 => 1,
+            DecoderVersion::V2 => 2,
+        }
     }
+}
 
-    fn each_binary_value(&self) -> impl Iterator<Item = u64> + '_ {
-        self.each_combination().map(|m| m.set)
+/// Which decoder versions to run for a `--part` argument: `1`, `2` or `both`
+fn parse_parts(arg: &str) -> Option<&'static [DecoderVersion]> {
+    match arg {
+        "1" => Some(&[DecoderVersion::V1]),
+        "2" => Some(&[DecoderVersion::V2]),
+        "both" => Some(&[DecoderVersion::V1, DecoderVersion::V2]),
+        _ => None,
     }
+}
+
+struct DockingEmulator {
+    version: DecoderVersion,
+    mask: Mask,
+    mem: HashMap<u64, u64>,
+}
 
-    fn each_combination(&self) -> impl Iterator<Item = Self> + '_ {
-        self.x_positions()
-            .powerset()
-            .map(move |xes| self.apply_x(xes))
+impl DockingEmulator {
+    fn new(version: DecoderVersion) -> Self {
+        Self {
+            version,
+            mask: Default::default(),
+            mem: Default::default(),
+        }
+    }
+
+    fn execute(&mut self, ins: &Instruction) {
+        match (ins, self.version) {
+            (&Instruction::SetMask(new_mask), _) => self.mask = new_mask,
+            (&Instruction::Assign { addr, val }, DecoderVersion::V1) => {
+                self.mem.insert(addr, self.mask.apply(val));
+            }
+            (&Instruction::Assign { addr, val }, DecoderVersion::V2) => {
+                for addr in self.mask.or(addr).each_binary_value() {
+                    self.mem.insert(addr, val);
+                }
+            }
+        }
+    }
+
+    fn run(&mut self, program: &Program) {
+        for ins in &program.instructions {
+            self.execute(ins);
+        }
+    }
+
+    fn memory_sum(&self) -> u64 {
+        self.mem.values().sum()
+    }
+
+    /// Every memory cell written so far, as `(address, value)` sorted by address
+    fn dump(&self) -> Vec<(u64, u64)> {
+        self.mem.iter().map(|(&addr, &val)| (addr, val)).sorted().collect()
     }
 }
 
-fn main() {
-    /*
-    println!(
-        "{:#?}",
-        Program::parse(include_str!("input.txt")).instructions
-    );
-    */
+/// Runs `program` on a fresh emulator
+fn emulate(version: DecoderVersion, program: &Program) -> DockingEmulator {
+    let mut emulator = DockingEmulator::new(version);
+    emulator.run(program);
+    emulator
+}
 
+/// The memory sum of a `DecoderVersion::V2` run, without going through every address: a write only
+/// counts for the addresses that no later write overwrites, so this goes through writes last to first,
+/// keeping track of the addresses written so far as a list of disjoint masks.
+///
+/// The sum is a `u128`: with many X bits it easily goes past a `u64` (36 X bits and a value of 2^28 already
+/// make 2^64), but every address is counted at most once and both addresses and values fit in 64 bits,
+/// so it stays below 2^128.
+fn part2_fast(program: &Program) -> u128 {
     let mut mask: Mask = Default::default();
-    let mut mem = HashMap::<u64, u64>::new();
-
-    let program = Program::parse(include_str!("input.txt"));
+    let mut writes = Vec::new();
     for ins in &program.instructions {
         match *ins {
             Instruction::SetMask(new_mask) => mask = new_mask,
-            Instruction::Assign { addr, val } => {
-                mem.insert(addr, mask.apply(val));
-            }
+            Instruction::Assign { addr, val } => writes.push((mask.or(addr), val)),
         }
     }
 
-    println!("Part 1:");
-    println!("  Answer: {}", mem.values().sum::<u64>());
-
-    let program = Program::parse(include_str!("input.txt"));
-    /*
-    println!("{:#?}", program.instructions);
+    let mut written: Vec<Mask> = Vec::new();
+    let mut sum = 0;
+    for (addresses, val) in writes.into_iter().rev() {
+        let mut unwritten = vec![addresses];
+        for done in &written {
+            unwritten = unwritten.iter().flat_map(|piece| piece.subtract(done)).collect();
+        }
+        sum += u128::from(val) * unwritten.iter().map(Mask::address_count).sum::<u128>();
+        written.extend(unwritten);
+    }
+    sum
+}
 
-    if let Instruction::SetMask(mask) = &program.instructions[0] {
-        println!("{:?} (mask)", mask);
-        let addr = 42;
-        println!("{:036b} (addr)", addr);
-        let mask = mask.or(addr);
-        println!("{:?} (combined)", mask);
+fn main() -> anyhow::Result<()> {
+    const USAGE: &str = "usage: day14 [--dump] [--part 1|2|both] [PATH | -]";
+    let (mut path, mut dump, mut parts) = (None, false, parse_parts("both").unwrap());
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // prints every nonzero memory cell before the answers
+            "--dump" => dump = true,
+            "--part" => parts = args.next().as_deref().and_then(parse_parts).context(USAGE)?,
+            _ if !arg.starts_with("--") => path = Some(arg),
+            _ => anyhow::bail!(USAGE),
+        }
+    }
 
-        println!("yields:");
-        for val in mask.each_binary_value() {
-            println!("{:036b} ({})", val, val);
+    // `day14 <path>` reads the program from a file (e.g. `day14/src/sample2.txt`) and `day14 -` from stdin,
+    // without arguments we use the embedded input.txt
+    let input = match path.as_deref() {
+        None => include_str!("input.txt").to_string(),
+        Some("-") => std::io::read_to_string(std::io::stdin()).context("couldn't read the program")?,
+        Some(path) => std::fs::read_to_string(path).with_context(|| format!("couldn't read {}", path))?,
+    };
+    let program = Program::parse(&input)?;
+
+    for &version in parts {
+        println!("Part {}:", version.part());
+        // the emulator writes every address a V2 mask stands for, only go through that to list them
+        if dump {
+            for (addr, val) in emulate(version, &program).dump().into_iter().filter(|&(_, val)| val != 0) {
+                println!("  mem[{}] = {}", addr, val);
+            }
         }
+        let answer = match version {
+            DecoderVersion::V1 => u128::from(emulate(version, &program).memory_sum()),
+            DecoderVersion::V2 => part2_fast(&program),
+        };
+        println!("  Answer: {}", answer);
     }
-    */
 
-    let mut mask: Mask = Default::default();
-    let mut mem = HashMap::<u64, u64>::new();
+    Ok(())
+}
 
-    for ins in &program.instructions {
-        match *ins {
-            Instruction::SetMask(new_mask) => mask = new_mask,
-            Instruction::Assign { addr, val } => {
-                for addr in mask.or(addr).each_binary_value() {
-                    mem.insert(addr, val);
-                }
+#[test]
+fn test_part2_fast() {
+    let program = Program::parse(include_str!("sample2.txt")).unwrap();
+    assert_eq!(emulate(DecoderVersion::V2, &program).memory_sum(), 208);
+    assert_eq!(part2_fast(&program), 208);
+
+    let program = Program::parse(include_str!("input.txt")).unwrap();
+    assert_eq!(part2_fast(&program), u128::from(emulate(DecoderVersion::V2, &program).memory_sum()));
+}
+
+#[test]
+fn test_part2_fast_random() {
+    use rand_xoshiro::rand_core::{RngCore, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(0x2545_f491_4f6c_dd1d);
+    let mut next = move || rng.next_u64();
+
+    // small programs, with at most a few X bits among the 8 lowest so that writes overlap a lot
+    for _ in 0..200 {
+        let mut input = String::new();
+        for _ in 0..(next() % 4 + 1) {
+            let mask: String = (0..36)
+                .map(|i| match (i < 28, next() % 3) {
+                    (true, _) => '0',
+                    (false, 0) => 'X',
+                    (false, 1) => '1',
+                    _ => '0',
+                })
+                .collect();
+            input += &format!("mask = {}\n", mask);
+            for _ in 0..(next() % 4 + 1) {
+                input += &format!("mem[{}] = {}\n", next() % 256, next() % 1000);
             }
         }
+        let program = Program::parse(&input).unwrap();
+        let emulated = emulate(DecoderVersion::V2, &program).memory_sum();
+        assert_eq!(part2_fast(&program), u128::from(emulated), "{}", input);
+    }
+}
+
+#[test]
+fn test_part2_fast_many_floating_bits() {
+    // 2^24 addresses, all set to 1
+    let input = "mask = 000000000000XXXXXXXXXXXXXXXXXXXXXXXX\nmem[0] = 1\n";
+    assert_eq!(part2_fast(&Program::parse(input).unwrap()), 1 << 24);
+
+    // then 2^24 addresses set to 3, half of which were set to 1, and 24 X bits again
+    let input = "mask = 000000000000XXXXXXXXXXXXXXXXXXXXXXXX\nmem[0] = 1\n\
+                 mask = 00000000000X0XXXXXXXXXXXXXXXXXXXXXXX\nmem[0] = 3\n";
+    assert_eq!(part2_fast(&Program::parse(input).unwrap()), 3 * (1 << 24) + (1 << 23));
+
+    // sums that don't fit in a u64: 2^28 at 2^36 addresses, the largest value at all 2^64 addresses of a 64-bit word
+    let input = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX\nmem[0] = 268435456\n";
+    assert_eq!(part2_fast(&Program::parse(input).unwrap()), 1 << 64);
+    let input = format!("mask = {}\nmem[0] = {}\n", "X".repeat(64), u64::MAX);
+    let program = Program::parse_with_width(&input, 64).unwrap();
+    assert_eq!(part2_fast(&program), (1 << 64) * u128::from(u64::MAX));
+}
+
+#[test]
+fn test_parse_errors() {
+    let err = Program::parse("mask = 00000000000000000000000000000000XXXX\nmem[foo] = 3\n").unwrap_err();
+    assert_eq!((err.line, err.text.as_str()), (2, "mem[foo] = 3"));
+
+    let err = Program::parse("mem[8] = 1\nmask = 000000000000000000000000000000000X02\nmem[8] = 1\n").unwrap_err();
+    assert_eq!((err.line, err.text.as_str()), (2, "mask = 000000000000000000000000000000000X02"));
+
+    let err = Program::parse("mask = 000000000000000000000000000000000X01\nmem[8]\nmem[9] = 1\n").unwrap_err();
+    assert_eq!((err.line, err.text.as_str()), (2, "mem[8]"));
+    assert_eq!(err.to_string(), r#"line 2: "mem[8]", expected one of "] = ", ['0'..='9']"#);
+
+    let err = Program::parse("mem[99999999999999999999] = 1").unwrap_err();
+    assert_eq!(err.line, 1);
+    assert!(err.expected.contains("a number that fits in 64 bits"), "{}", err);
+}
+
+#[test]
+fn test_36_bits() {
+    let err = Program::parse("mask = 00000000000000000000000000000X1001X\nmem[42] = 100\n").unwrap_err();
+    assert_eq!(err.line, 1);
+    assert!(err.expected.contains("a mask with one character per bit of a word"), "{}", err);
+    let err = Program::parse("mask = 0000000000000000000000000000000X1001X\nmem[42] = 100\n").unwrap_err();
+    assert_eq!(err.line, 1);
+    assert!(err.expected.contains("a mask with one character per bit of a word"), "{}", err);
+
+    let mask = "mask = 000000000000000000000000000000X1001X\n";
+    let err = Program::parse(&format!("{}mem[42] = {}\n", mask, 1_u64 << 36)).unwrap_err();
+    assert_eq!(err.line, 2);
+    assert!(err.expected.contains("a number that fits in a word"), "{}", err);
+    let err = Program::parse(&format!("{}mem[{}] = 100\n", mask, 1_u64 << 36)).unwrap_err();
+    assert_eq!(err.line, 2);
+    assert!(err.expected.contains("a number that fits in a word"), "{}", err);
+
+    let program = Program::parse(&format!("{}mem[{}] = {}\n", mask, (1_u64 << 36) - 1, (1_u64 << 36) - 1)).unwrap();
+    assert_eq!(emulate(DecoderVersion::V1, &program).memory_sum(), 0b110011);
+    assert_eq!(part2_fast(&program), 4 * ((1_u128 << 36) - 1));
+}
+
+#[test]
+fn test_emulator() {
+    let program = Program::parse(include_str!("sample1.txt")).unwrap();
+    let emulator = emulate(DecoderVersion::V1, &program);
+    assert_eq!(emulator.memory_sum(), 165);
+    assert_eq!(emulator.dump(), vec![(7, 101), (8, 64)]);
+
+    let program = Program::parse(include_str!("sample2.txt")).unwrap();
+    let mut emulator = DockingEmulator::new(DecoderVersion::V2);
+    for ins in &program.instructions[..2] {
+        emulator.execute(ins);
+    }
+    assert_eq!(emulator.dump(), vec![(26, 100), (27, 100), (58, 100), (59, 100)]);
+    for ins in &program.instructions[2..] {
+        emulator.execute(ins);
+    }
+    assert_eq!(emulator.memory_sum(), 208);
+    assert_eq!(
+        emulator.dump(),
+        vec![(16, 1), (17, 1), (18, 1), (19, 1), (24, 1), (25, 1), (26, 1), (27, 1), (58, 100), (59, 100)]
+    );
+}
+
+#[test]
+fn test_display_round_trip() {
+    let mask = "000000000000000000000000000000X1001X";
+    let program = Program::parse(&format!("mask = {}\nmem[42] = 100\n", mask)).unwrap();
+    assert_eq!(program.instructions[0].to_string(), format!("mask = {}", mask));
+    assert_eq!(format!("{:?}", program.instructions[0]), format!("mask: {}", mask));
+    assert_eq!(program.instructions[1].to_string(), "mem[42] = 100");
+
+    for input in [include_str!("sample1.txt"), include_str!("sample2.txt"), include_str!("input.txt")] {
+        let program = Program::parse(input).unwrap();
+        assert_eq!(Program::parse(&program.to_string()).unwrap(), program);
+        assert_eq!(program.to_string().trim_end(), input.trim_end());
+    }
+}
+
+#[test]
+fn test_parse_parts() {
+    assert_eq!(parse_parts("1"), Some(&[DecoderVersion::V1][..]));
+    assert_eq!(parse_parts("2"), Some(&[DecoderVersion::V2][..]));
+    assert_eq!(parse_parts("both"), Some(&[DecoderVersion::V1, DecoderVersion::V2][..]));
+    for arg in ["", "3", "0", "Both", "1,2"] {
+        assert_eq!(parse_parts(arg), None, "{:?}", arg);
+    }
+}
+
+#[test]
+fn test_each_binary_value() {
+    let mask = |input: &str| match Program::parse(&format!("mask = {}", input)).unwrap().instructions[0] {
+        Instruction::SetMask(mask) => mask,
+        _ => unreachable!(),
+    };
+
+    let addresses: Vec<_> = mask("000000000000000000000000000000X1001X").or(42).each_binary_value().collect();
+    assert_eq!(addresses, vec![26, 27, 58, 59]);
+    let addresses: Vec<_> = mask("00000000000000000000000000000000X0XX").or(26).each_binary_value().collect();
+    assert_eq!(addresses, vec![16, 17, 18, 19, 24, 25, 26, 27]);
+
+    // a single value without X bits, every value with only X bits
+    let addresses: Vec<_> = mask("111111111111111111111111111111111111").or(0).each_binary_value().collect();
+    assert_eq!(addresses, vec![(1 << 36) - 1]);
+    assert_eq!(mask("000000000000000000000000XXXXXXXXXXXX").or(0).each_binary_value().count(), 1 << 12);
+    assert!(mask("000000000000000000000000XXXXXXXXXXXX").or(0).each_binary_value().eq(0..1 << 12));
+}
+
+#[test]
+fn test_word_width() {
+    let input = "mask = X10X\nmem[3] = 9\nmask = 0X1X\nmem[8] = 5\n";
+    assert!(Program::parse(input).is_err());
+    let program = Program::parse_with_width(input, 4).unwrap();
+    assert_eq!(program.to_string(), input);
+    match program.instructions[0] {
+        Instruction::SetMask(mask) => assert_eq!(mask.width(), 4),
+        _ => unreachable!(),
     }
 
-    println!("Part 2:");
-    println!("  Answer: {}", mem.values().sum::<u64>());
+    // 9 = 1001 becomes 1101 = 13 at 3, then 5 = 0101 becomes 0111 = 7 at 8
+    let emulator = emulate(DecoderVersion::V1, &program);
+    assert_eq!(emulator.dump(), vec![(3, 13), (8, 7)]);
+    assert_eq!(emulator.memory_sum(), 20);
+
+    // 9 at X11X (6, 7, 14 and 15), then 5 at 1X1X (10, 11, 14 and 15)
+    let emulator = emulate(DecoderVersion::V2, &program);
+    assert_eq!(emulator.dump(), vec![(6, 9), (7, 9), (10, 5), (11, 5), (14, 5), (15, 5)]);
+    assert_eq!(emulator.memory_sum(), 38);
+    assert_eq!(part2_fast(&program), 38);
+
+    // words are 4 bits
+    assert!(Program::parse_with_width("mask = X10X\nmem[16] = 9\n", 4).is_err());
+    assert!(Program::parse_with_width("mask = X10X\nmem[3] = 16\n", 4).is_err());
+    assert!(Program::parse_with_width("mask = X10XX\nmem[3] = 9\n", 4).is_err());
+
+    // and up to 64
+    let mask = "X".repeat(62) + "01";
+    let program = Program::parse_with_width(&format!("mask = {}\nmem[{}] = 3\n", mask, u64::MAX), 64).unwrap();
+    assert_eq!(emulate(DecoderVersion::V1, &program).memory_sum(), 1);
+    assert_eq!(part2_fast(&program), 3 << 62);
 }
@@ -1,11 +1,13 @@
-use std::collections::HashMap;
+use anyhow::Context;
+use log::info;
 
-type Turn = usize;
-type Number = i64;
+// Numbers spoken are never more than the number of turns played, which is at most u32::MAX
+type Turn = u32;
+type Number = u32;
 
 struct NumberGame {
-    last_turns: HashMap<Number, Turn>,
-    prev_turns: HashMap<Number, Turn>,
+    // indexed by number: turn + 1 it was last spoken on, 0 for never (the last number spoken isn't in there yet)
+    last_seen: Vec<Turn>,
     starting_numbers: Vec<Number>,
     next_turn: Turn,
     last_spoken: Number
@@ -14,9 +16,8 @@ struct NumberGame {
 impl NumberGame {
     fn new(starting_numbers: &[Number]) -> Self {
         NumberGame {
-            last_turns: HashMap::new(),
-            prev_turns: HashMap::new(),
-            starting_numbers: starting_numbers.iter().cloned().collect(),
+            last_seen: Vec::new(),
+            starting_numbers: starting_numbers.to_vec(),
             next_turn: 0,
             last_spoken: 0
         }
@@ -27,20 +28,30 @@ impl Iterator for NumberGame {
     type Item = Number;
 
     fn next(&mut self) -> Option<Number> {
-        let next_number = if self.next_turn < self.starting_numbers.len() {
-            self.starting_numbers[self.next_turn]
+        let turn = self.next_turn;
+        if turn == Turn::MAX {
+            // u32::MAX turns played, turn + 1 wouldn't fit in the table anymore
+            return None;
+        }
+        let index = self.last_spoken as usize;
+        let next_number = if (turn as usize) < self.starting_numbers.len() {
+            self.starting_numbers[turn as usize]
         } else {
-            let last = self.last_turns.get(&self.last_spoken).unwrap();
-            match self.prev_turns.get(&self.last_spoken) {
-                None => 0,
-                Some(prev) => (last - prev) as Number
+            // last_spoken was spoken on the previous turn, the gap is with the time before that
+            match self.last_seen.get(index) {
+                None | Some(0) => 0,
+                Some(&seen) => turn - seen
             }
         };
 
-        if let Some(prev) = self.last_turns.get(&next_number) {
-            self.prev_turns.insert(next_number, *prev);
+        // only now does the previous turn go in the table
+        if turn > 0 {
+            if index >= self.last_seen.len() {
+                // spoken numbers are at most the number of turns so far, so this grows like the turns do
+                self.last_seen.resize(index + 1, 0);
+            }
+            self.last_seen[index] = turn;
         }
-        self.last_turns.insert(next_number, self.next_turn);
         self.last_spoken = next_number;
         self.next_turn += 1;
 
@@ -48,24 +59,82 @@ impl Iterator for NumberGame {
     }
 }
 
+#[derive(thiserror::Error, Debug, PartialEq)]
+enum GameError {
+    #[error("the game needs at least one starting number")]
+    EmptyStart,
+    #[error("turns are counted from 1, there is no turn 0")]
+    ZeroTarget,
+    #[error("can't play {0} turns, the game stops after {}", Turn::MAX)]
+    TooManyTurns(usize),
+}
 
-fn number_spoken_at_index(starting_numbers: &[Number], target_index: Turn) -> Number {
-    NumberGame::new(starting_numbers)
+fn check_game(starting_numbers: &[Number], target_index: usize) -> Result<(), GameError> {
+    if starting_numbers.is_empty() {
+        Err(GameError::EmptyStart)
+    } else if target_index == 0 {
+        Err(GameError::ZeroTarget)
+    } else if Turn::try_from(target_index).is_err() {
+        Err(GameError::TooManyTurns(target_index))
+    } else {
+        Ok(())
+    }
+}
+
+// target_index counts from 1, and a turn that still has a starting number gives that number
+fn number_spoken_at_index(starting_numbers: &[Number], target_index: usize) -> Result<Number, GameError> {
+    check_game(starting_numbers, target_index)?;
+    Ok(NumberGame::new(starting_numbers)
         .nth(target_index - 1)
-        .unwrap()
+        .expect("the game never ends"))
+}
+
+/// Same as number_spoken_at_index(), calling `f` with the number of turns played every `every` turns
+/// (and never if `every` is 0)
+fn number_spoken_with_progress(
+    starting_numbers: &[Number],
+    target_index: usize,
+    every: usize,
+    mut f: impl FnMut(usize),
+) -> Result<Number, GameError> {
+    check_game(starting_numbers, target_index)?;
+    if every == 0 {
+        return number_spoken_at_index(starting_numbers, target_index);
+    }
+    // playing `every` turns at a time keeps the check out of the game's loop
+    let mut game = NumberGame::new(starting_numbers);
+    let (mut turn, mut number) = (0, None);
+    while turn < target_index {
+        let turns = every.min(target_index - turn);
+        number = game.nth(turns - 1);
+        turn += turns;
+        if turn % every == 0 {
+            f(turn);
+        }
+    }
+    Ok(number.expect("the game never ends"))
 }
 
-fn part1(starting_numbers: &[Number]) -> Number {
+// how often to log progress on long games
+const PROGRESS_EVERY: usize = 5_000_000;
+
+fn part1(starting_numbers: &[Number]) -> Result<Number, GameError> {
     number_spoken_at_index(starting_numbers, 2020)
 }
 
-fn part2(starting_numbers: &[Number]) -> Number {
-    number_spoken_big(starting_numbers, 30000000)
+fn part2(starting_numbers: &[Number]) -> Result<Number, GameError> {
+    number_spoken_with_progress(starting_numbers, 30000000, PROGRESS_EVERY, |turn| {
+        info!("part 2: {} turns played", turn)
+    })
 }
 
 // number_spoken_big() uses a dynamic programming implementation
-fn number_spoken_big(starting_numbers: &[Number], last: usize) -> Number {
-    let mut turns_spoken: HashMap<Number, usize> = starting_numbers
+// (kept, like number_spoken_fast(), to check NumberGame against, with the i64 numbers and usize turns it started with)
+#[cfg(test)]
+fn number_spoken_big(starting_numbers: &[i64], last: usize) -> i64 {
+    use std::collections::HashMap;
+
+    let mut turns_spoken: HashMap<i64, usize> = starting_numbers
         .iter()
         .take(starting_numbers.len() - 1)
         .enumerate()
@@ -78,24 +147,235 @@ fn number_spoken_big(starting_numbers: &[Number], last: usize) -> Number {
             None => 0,
         };
         turns_spoken.insert(last_spoken, i - 1);
-        last_spoken = newly_spoken as Number;
+        last_spoken = newly_spoken as i64;
+    }
+    last_spoken
+}
+
+// number_spoken_fast() is number_spoken_big() with a Vec instead of a HashMap: a number spoken is either a starting
+// number or the gap between two turns, so the table never needs more entries than that
+#[cfg(test)]
+fn number_spoken_fast(starting: &[Number], target: Turn) -> Number {
+    let len = starting.iter().map(|&n| n as usize + 1).max().unwrap_or(0).max(target as usize);
+    // turn + 1 each number was last spoken on, 0 for never
+    let mut last_seen: Vec<Turn> = vec![0; len];
+    for (turn, &n) in starting.iter().take(starting.len() - 1).enumerate() {
+        last_seen[n as usize] = turn as Turn + 1;
+    }
+    let mut last_spoken = *starting.last().unwrap();
+    for turn in starting.len() as Turn..target {
+        // last_spoken was spoken on turn - 1, stored as turn
+        let seen = std::mem::replace(&mut last_seen[last_spoken as usize], turn);
+        last_spoken = if seen == 0 { 0 } else { turn - seen };
     }
     last_spoken
 }
 
-fn main() {
-    let input = [0,5,4,1,10,14,7];
-    println!("part 1 {}", part1(&input));
-    println!("part 2 {}", part2(&input));
+#[derive(thiserror::Error, Debug, PartialEq)]
+enum ParseError {
+    #[error("no starting numbers")]
+    Empty,
+    #[error("starting number #{} isn't a non-negative number: {text:?}", .index + 1)]
+    NotANumber { index: usize, text: String },
+    // the game looks back at the last time a number was spoken, a starting number showing up twice would mess that up
+    #[error("{0} is a starting number more than once")]
+    Duplicate(Number),
+}
+
+/// Parses comma-separated starting numbers, e.g. `0,3,6`
+fn parse_starting_numbers(input: &str) -> Result<Vec<Number>, ParseError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    let mut numbers = Vec::new();
+    for (index, text) in input.split(',').enumerate() {
+        let number = text.trim().parse::<Number>().map_err(|_| ParseError::NotANumber {
+            index,
+            text: text.to_string(),
+        })?;
+        if numbers.contains(&number) {
+            return Err(ParseError::Duplicate(number));
+        }
+        numbers.push(number);
+    }
+    Ok(numbers)
+}
+
+fn main() -> anyhow::Result<()> {
+    const USAGE: &str = "usage: day15 [--verbose] [--turns N] [STARTING_NUMBERS | -]";
+    let (mut input, mut turns, mut verbose) = (None, None, false);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // the number spoken on turn N, instead of turns 2020 and 30000000
+            "--turns" => {
+                let n = args.next().context(USAGE)?;
+                turns = Some(n.parse::<usize>().with_context(|| format!("invalid number of turns {:?}", n))?);
+            }
+            // logs progress every 5 million turns, like RUST_LOG=info does
+            "--verbose" => verbose = true,
+            _ if !arg.starts_with("--") && input.is_none() => input = Some(arg),
+            _ => anyhow::bail!(USAGE),
+        }
+    }
+
+    let mut logger = env_logger::Builder::from_default_env();
+    if verbose {
+        logger.filter_level(log::LevelFilter::Info);
+    }
+    logger.init();
+
+    // `day15 0,3,6` plays with those starting numbers and `day15 -` reads them from stdin,
+    // without arguments we use the puzzle input
+    let input = match input.as_deref() {
+        None => vec![0,5,4,1,10,14,7],
+        Some("-") => parse_starting_numbers(&std::io::read_to_string(std::io::stdin())?)?,
+        Some(list) => parse_starting_numbers(list)?,
+    };
+
+    match turns {
+        Some(turns) => {
+            let number = number_spoken_with_progress(&input, turns, PROGRESS_EVERY, |turn| {
+                info!("{} turns played out of {}", turn, turns)
+            })?;
+            println!("turn {} {}", turns, number);
+        }
+        None => {
+            println!("part 1 {}", part1(&input)?);
+            println!("part 2 {}", part2(&input)?);
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_starting_numbers() {
+        assert_eq!(parse_starting_numbers("0,3,6"), Ok(vec![0,3,6]));
+        assert_eq!(parse_starting_numbers("0,5,4,1,10,14,7\n"), Ok(vec![0,5,4,1,10,14,7]));
+        assert_eq!(
+            parse_starting_numbers("0,,6"),
+            Err(ParseError::NotANumber { index: 1, text: "".to_string() })
+        );
+        assert_eq!(
+            parse_starting_numbers("0,-3,6").unwrap_err().to_string(),
+            "starting number #2 isn't a non-negative number: \"-3\""
+        );
+        assert_eq!(parse_starting_numbers(""), Err(ParseError::Empty));
+        assert_eq!(parse_starting_numbers("3,1,3"), Err(ParseError::Duplicate(3)));
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut turns = Vec::new();
+        assert_eq!(number_spoken_with_progress(&[0,3,6], 2020, 500, |turn| turns.push(turn)), Ok(436));
+        assert_eq!(turns, vec![500, 1000, 1500, 2000]);
+
+        let mut turns = Vec::new();
+        let number = number_spoken_with_progress(&[0,3,6], 2000, 500, |turn| turns.push(turn));
+        assert_eq!(number, number_spoken_at_index(&[0,3,6], 2000));
+        assert_eq!(turns, vec![500, 1000, 1500, 2000]);
+
+        // every turn, including the starting numbers
+        let mut calls = 0;
+        assert_eq!(number_spoken_with_progress(&[0,3,6], 10, 1, |_| calls += 1), Ok(0));
+        assert_eq!(calls, 10);
+
+        let mut calls = 0;
+        assert_eq!(number_spoken_with_progress(&[0,3,6], 2020, 0, |_| calls += 1), Ok(436));
+        assert_eq!(calls, 0);
+    }
+
+    #[test]
+    fn test_degenerate_games() {
+        assert_eq!(number_spoken_at_index(&[], 2020), Err(GameError::EmptyStart));
+        assert_eq!(number_spoken_at_index(&[0,3,6], 0), Err(GameError::ZeroTarget));
+        assert_eq!(number_spoken_with_progress(&[], 10, 5, |_| {}), Err(GameError::EmptyStart));
+        let mut calls = 0;
+        assert_eq!(number_spoken_with_progress(&[0,3,6], 0, 1, |_| calls += 1), Err(GameError::ZeroTarget));
+        assert_eq!(calls, 0);
+
+        // turns that still have a starting number
+        for (target, expected) in [(1, 5), (2, 4), (3, 1), (4, 10)] {
+            assert_eq!(number_spoken_at_index(&[5,4,1,10,14,7], target), Ok(expected));
+            assert_eq!(number_spoken_with_progress(&[5,4,1,10,14,7], target, 2, |_| {}), Ok(expected));
+        }
+        assert_eq!(number_spoken_at_index(&[7], 1), Ok(7));
+        assert_eq!(number_spoken_at_index(&[7], 2), Ok(0));
+    }
+
+    #[test]
+    fn test_first_turns() {
+        // as listed in the puzzle
+        let game: Vec<Number> = NumberGame::new(&[0,3,6]).take(10).collect();
+        assert_eq!(game, vec![0,3,6,0,3,3,1,0,4,0]);
+    }
+
     #[test]
     fn test_number_spoken_at_index() {
-        assert_eq!(number_spoken_at_index(&[0,3,6], 10), 0);
+        assert_eq!(number_spoken_at_index(&[0,3,6], 10), Ok(0));
         assert_eq!(number_spoken_big(&[0,3,6], 30000000), 175594);
+        assert_eq!(number_spoken_fast(&[0,3,6], 30000000), 175594);
+    }
+
+    #[test]
+    fn test_number_spoken_fast() {
+        for (starting, expected) in [
+            ([1,3,2], 1),
+            ([2,1,3], 10),
+            ([1,2,3], 27),
+            ([2,3,1], 78),
+            ([3,2,1], 438),
+            ([3,1,2], 1836),
+        ] {
+            assert_eq!(number_spoken_fast(&starting, 2020), expected);
+            assert_eq!(number_spoken_at_index(&starting, 2020), Ok(expected));
+            for target in [4, 10, 2020, 100000] {
+                assert_eq!(
+                    number_spoken_fast(&starting, target) as i64,
+                    number_spoken_big(&starting.map(i64::from), target as usize)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_number_game_matches_direct() {
+        for starting in [[0,3,6], [1,3,2], [2,1,3], [1,2,3], [2,3,1], [3,2,1], [3,1,2]] {
+            let game: Vec<Number> = NumberGame::new(&starting).take(2020).collect();
+            assert_eq!(&game[..3], &starting[..]);
+            for (target, &number) in game.iter().enumerate().skip(3) {
+                assert_eq!(number, number_spoken_fast(&starting, target as Turn + 1));
+            }
+        }
+
+        assert_eq!(number_spoken_at_index(&[0,3,6], 30000000), Ok(175594));
+        assert_eq!(number_spoken_at_index(&[3,1,2], 30000000), Ok(362));
+    }
+
+    #[test]
+    fn test_u32_matches_i64() {
+        // a few million turns: spoken numbers get into the millions too
+        for starting in [vec![0,3,6], vec![0,5,4,1,10,14,7]] {
+            let expected = number_spoken_big(&starting.iter().map(|&n| i64::from(n)).collect::<Vec<_>>(), 3_000_000);
+            assert_eq!(number_spoken_at_index(&starting, 3_000_000).map(i64::from), Ok(expected));
+        }
+
+        let too_many = Turn::MAX as usize + 1;
+        assert_eq!(number_spoken_at_index(&[0,3,6], too_many), Err(GameError::TooManyTurns(too_many)));
+        assert_eq!(
+            number_spoken_with_progress(&[0,3,6], too_many, 1, |_| {}),
+            Err(GameError::TooManyTurns(too_many))
+        );
+
+        // the game stops right after turn u32::MAX
+        let mut game = NumberGame::new(&[0,3,6]);
+        game.next_turn = Turn::MAX - 1;
+        assert!(game.next().is_some());
+        assert_eq!(game.next(), None);
     }
 }
@@ -127,7 +127,7 @@ pub fn match_literal<'a>(expected: &'static str) -> impl Parser<'a, ()> {
         }
 }
 
-pub fn identifier(input: &str) -> ParseResult<String> {
+pub fn identifier(input: &str) -> ParseResult<'_, String> {
     let mut matched = String::new();
     let mut chars = input.chars();
 
@@ -136,7 +136,7 @@ pub fn identifier(input: &str) -> ParseResult<String> {
         _ => return Err(input)
     }
 
-    while let Some(next) = chars.next() {
+    for next in chars {
         if next.is_alphabetic() || next == '-' {
             matched.push(next);
         } else {
@@ -148,7 +148,7 @@ pub fn identifier(input: &str) -> ParseResult<String> {
     Ok((&input[next_index..], matched))
 }
 
-pub fn word_ref(input: &str) -> ParseResult<&str> {
+pub fn word_ref(input: &str) -> ParseResult<'_, &str> {
     let mut matched = 0;
     let mut chars = input.chars();
 
@@ -157,7 +157,7 @@ pub fn word_ref(input: &str) -> ParseResult<&str> {
         _ => return Err(input)
     }
 
-    while let Some(next) = chars.next() {
+    for next in chars {
         if next.is_alphabetic() {
             matched += 1;
         } else {
@@ -312,7 +312,7 @@ pub fn sep_by<'a, PA, A, PS, S>(parser: PA, sep_parser: PS) -> impl Parser<'a, V
     }
 }
 
-pub fn any_char(input: &str) -> ParseResult<char> {
+pub fn any_char(input: &str) -> ParseResult<'_, char> {
     match input.chars().next() {
         Some(next) => Ok((&input[next.len_utf8()..], next)),
         _ => Err(input)
@@ -409,14 +409,18 @@ pub fn whitespace_wrap<'a, P, A>(parser: P) -> impl Parser<'a, A>
 }
 
 
-pub fn integer(input: &str) -> ParseResult<i64> {
-    let digit_as_num = any_char.pred(|c| c.is_digit(10)).map(|d| (d as i64) - 48);
+pub fn integer(input: &str) -> ParseResult<'_, i64> {
+    let digit_as_num = any_char.pred(|c| c.is_ascii_digit()).map(|d| (d as i64) - 48);
 
     if let Ok((rest, first_digit)) = digit_as_num.parse(input) {
         let mut i = first_digit;
         let mut remainder = rest;
         while let Ok((rest, next_digit)) = digit_as_num.parse(remainder) {
-            i = i * 10 + next_digit;
+            // a number too big for an i64 doesn't parse, rather than wrapping around
+            i = match i.checked_mul(10).and_then(|i| i.checked_add(next_digit)) {
+                Some(i) => i,
+                None => return Err(input),
+            };
             remainder = rest;
         }
         Ok((remainder, i))
@@ -541,4 +545,20 @@ mod tests {
             integer.parse("123foo")
         );
     }
+
+    #[test]
+    fn integer_parser_fails_on_overflow() {
+        assert_eq!(
+            Ok(("", i64::MAX)),
+            integer.parse("9223372036854775807")
+        );
+        assert_eq!(
+            Err("9223372036854775808"),
+            integer.parse("9223372036854775808")
+        );
+        assert_eq!(
+            Err("99999999999999999999-3"),
+            integer.parse("99999999999999999999-3")
+        );
+    }
 }